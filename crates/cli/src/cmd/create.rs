@@ -1,53 +1,354 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+  fs,
+  path::Path,
+  time::{SystemTime, UNIX_EPOCH},
+};
 
 use inquire::Confirm;
 use proplate_core::{
-  gen::bootstrap::bootstrap,
-  template::{config::TemplateConf, inquirer::Input, resolver::clone_template, Template},
+  gen::{
+    answers::{load_answers, AnswersRecord},
+    bootstrap::{
+      bootstrap, check_dest, cleanup, dry_run, BootstrapOptions, ConflictPolicy, Context,
+      DryRunReport,
+    },
+    builtins,
+  },
+  template::{
+    config::TemplateConf, inquirer::Input, interpolation::interpolate_with_env,
+    resolver::clone_template_at, Template,
+  },
+};
+use proplate_errors::{
+  ArgErrorKind, CliErrorKind, ProplateError, ProplateErrorKind, ProplateResult,
 };
-use proplate_errors::{CliErrorKind, ProplateError, ProplateErrorKind, ProplateResult};
 use proplate_integration::git;
 use proplate_tui::logger;
+use uuid::Uuid;
 
 #[derive(Debug, Default)]
 pub struct CreateOptions {
   pub git: bool,
+  /// "key=value" pairs supplied through "--arg"
+  pub args: Vec<String>,
+  /// Answers file supplied through "--answers"
+  pub answers: Option<String>,
+  /// Fail instead of prompting for missing args
+  pub no_input: bool,
+  /// Write the answers record in the generated project
+  pub record: bool,
+  /// Only report what would be generated
+  pub dry_run: bool,
+  /// What to do with the files already in dest
+  pub on_conflict: ConflictPolicy,
+  /// Fail on vars that cannot be bound
+  pub strict: bool,
+  /// "YYYY-MM-DD" date used instead of today's by the builtins
+  pub now: Option<String>,
 }
 
-type Context = HashMap<String, String>;
-
 /// Create project starter
 /// entrypoint for cli since it has lot more interaction:D
 pub fn create(source: &str, dest: &str, options: CreateOptions) -> ProplateResult<()> {
+  let supplied = supplied_args(&options)?;
+  check_dest_early(dest, &options)?;
   println!("{}", logger::title("Setup template"));
-  let mut fork = fork_template(source, dest)?;
-  let ctx = prompt_args(&fork)?;
+  let fork = fork_template(source, dest, None)?;
+  generate(fork, dest, supplied, &options)
+}
+
+/// Regenerates the project described by an answers record, without prompting
+pub fn replay(record: &str, dest: &str, options: CreateOptions) -> ProplateResult<()> {
+  let record = AnswersRecord::load(Path::new(record))?;
+  check_dest_early(dest, &options)?;
+  println!("{}", logger::title("Setup template"));
+  let fork = fork_template(&record.fork_source, dest, record.commit.as_deref())?;
+  let options = CreateOptions {
+    no_input: true,
+    ..options
+  };
+  generate(fork, dest, record.context(), &options)
+}
+
+fn generate(
+  mut fork: Template,
+  dest: &str,
+  supplied: Context,
+  options: &CreateOptions,
+) -> ProplateResult<()> {
+  let ctx = (|| -> ProplateResult<Context> {
+    let mut supplied = supplied;
+    for (key, value) in builtins(&fork, dest, options)? {
+      // recorded or supplied ones are kept
+      supplied.entry(key).or_insert(value);
+    }
+    prompt_args(&fork, supplied, options.no_input)
+  })()
+  .inspect_err(|_| {
+    if cleanup(&fork).is_err() {
+      println!("{}", logger::warn("Unable to cleanup"));
+    }
+  })?;
+
+  let bootstrap_options = BootstrapOptions {
+    on_conflict: options.on_conflict,
+    strict: options.strict,
+  };
+
+  if options.dry_run {
+    let report = dry_run(&mut fork, dest, &ctx, &bootstrap_options)?;
+    print_dry_run(&report, dest);
+    return Ok(());
+  }
 
   if options.git {
     init_git_repo(&fork.base_path)?
   }
 
-  bootstrap(&mut fork, dest, &ctx)?;
+  bootstrap(&mut fork, dest, &ctx, &bootstrap_options)?;
+
+  if options.record {
+    AnswersRecord::new(&fork, &ctx).write(Path::new(dest))?;
+  }
 
   Ok(())
 }
 
+/// Values of the [builtins::BUILTIN_VARS]
+fn builtins(template: &Template, dest: &str, options: &CreateOptions) -> ProplateResult<Context> {
+  let (year, month, day) = match &options.now {
+    Some(now) => parse_date(now).ok_or_else(|| {
+      ProplateError::create(ProplateErrorKind::Arg {
+        key: "now".into(),
+        kind: ArgErrorKind::InvalidValue { value: now.clone() },
+      })
+      .with_ctx("cli::create::builtins")
+      .with_cause("Expected a date such as 2024-01-31")
+    })?,
+    None => today(),
+  };
+
+  let mut ctx = Context::from([
+    (builtins::YEAR.to_string(), (year as i64).into()),
+    (
+      builtins::DATE.to_string(),
+      format!("{:04}-{:02}-{:02}", year, month, day).into(),
+    ),
+    (
+      builtins::UUID.to_string(),
+      Uuid::new_v4().to_string().into(),
+    ),
+    (
+      builtins::TEMPLATE_ID.to_string(),
+      template.conf.id.clone().into(),
+    ),
+  ]);
+
+  let dest_name = std::path::absolute(dest).ok().and_then(|path| {
+    path
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+  });
+  if let Some(name) = dest_name {
+    ctx.insert(builtins::DEST_NAME.into(), name.into());
+  }
+
+  // left undefined when git isn't configured
+  for (key, config) in [
+    (builtins::GIT_USER_NAME, "user.name"),
+    (builtins::GIT_USER_EMAIL, "user.email"),
+  ] {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    if let Ok(value) = git::exec_cmd_output(["config", config], &cwd) {
+      ctx.insert(key.into(), value.into());
+    }
+  }
+
+  Ok(ctx)
+}
+
+/// (year, month, day) of "YYYY-MM-DD", the time of a full datetime e.g "2024-01-31T10:00:00Z"
+/// is ignored
+fn parse_date(date: &str) -> Option<(i32, u32, u32)> {
+  let time = date.get(10..)?;
+  let is_time = |time: &str| {
+    time.strip_prefix('T').is_some_and(|time| {
+      !time.is_empty()
+        && time
+          .chars()
+          .all(|c| c.is_ascii_digit() || ":.+-Z".contains(c))
+    })
+  };
+  if !time.is_empty() && !is_time(time) {
+    return None;
+  }
+
+  let mut parts = date[..10].splitn(3, '-');
+  let year = parts.next().filter(|y| y.len() == 4)?.parse().ok()?;
+  let month = parts.next().filter(|m| m.len() == 2)?.parse().ok()?;
+  let day = parts.next().filter(|d| d.len() == 2)?.parse().ok()?;
+  ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day))
+    .then_some((year, month, day))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+  let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+  match month {
+    2 if leap => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+/// Current UTC date, as (year, month, day)
+fn today() -> (i32, u32, u32) {
+  let secs = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs() as i64);
+  civil_from_days(secs.div_euclid(86400))
+}
+
+/// Days since 1970-01-01 to (year, month, day), see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+  let z = days + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + (month <= 2) as i64;
+
+  (year as i32, month as u32, day as u32)
+}
+
+/// Fails before any prompt rather than after the user answered everything, a dry run failing
+/// just like the actual generation would
+fn check_dest_early(dest: &str, options: &CreateOptions) -> ProplateResult<()> {
+  check_dest(dest, options.on_conflict)
+}
+
+fn print_dry_run(report: &DryRunReport, dest: &str) {
+  println!(
+    "{}",
+    logger::title(&format!("Dry run, nothing was written to '{}'", dest))
+  );
+
+  println!("{}", logger::step("Additional operations:"));
+  for op in &report.operations {
+    println!("  {}", op);
+  }
+
+  println!("{}", logger::step("Files to create:"));
+  for file in &report.created {
+    println!("  + {}", file.display());
+  }
+
+  println!("{}", logger::step("Files to overwrite:"));
+  for file in &report.overwritten {
+    println!("  ~ {}", file.display());
+  }
+
+  if !report.skipped.is_empty() {
+    println!("{}", logger::step("Existing files to keep:"));
+    for file in &report.skipped {
+      println!("  = {}", file.display());
+    }
+  }
+}
+
 /// Create copy of a template in a tempdir
-fn fork_template(from: &str, dest: &str) -> ProplateResult<Template> {
+fn fork_template(from: &str, dest: &str, commit: Option<&str>) -> ProplateResult<Template> {
   println!("{}", logger::step("Finding template..."));
-  clone_template(from, dest)
+  clone_template_at(from, dest, commit)
 }
 
-/// Interactively prompts args under "meta.args"
-fn prompt_args(template: &Template) -> ProplateResult<Context> {
+/// Resolves args under "meta.args" from the supplied answers
+/// and interactively prompts the ones that are still missing
+pub fn prompt_args(
+  template: &Template,
+  mut supplied: Context,
+  no_input: bool,
+) -> ProplateResult<Context> {
   let mut ctx = Context::new();
   let TemplateConf { args, .. } = &template.conf;
 
   println!("{}", logger::title("Template initialization:"));
 
   for arg in args {
-    let input = Input::from(arg);
-    ctx.insert(input.get_attr().name.clone(), input.prompt());
+    // "when" and defaults may refer to the previous answers, the supplied vars (builtins
+    // included) and, for defaults only, the environment
+    let scope = supplied.clone().into_iter().chain(ctx.clone()).collect();
+    let default = arg
+      .default_value
+      .as_ref()
+      .map(|default| interpolate_with_env(default, &scope));
+
+    if !arg.is_relevant(&scope)? {
+      // skipped args fall back to their default or remain undefined
+      let fallback = default
+        .map(|default| arg.validate(default.into()))
+        .transpose()?;
+      let ignored = supplied
+        .remove(&arg.key)
+        .filter(|value| arg.validate(value.clone()).ok() != fallback);
+      if let Some(value) = ignored {
+        println!(
+          "{}",
+          logger::warn(&format!(
+            "'{}' is not asked as its \"when\" conditions don't hold, '{}' is ignored",
+            arg.key, value
+          ))
+        );
+      }
+      if let Some(fallback) = fallback {
+        ctx.insert(arg.key.clone(), fallback);
+      }
+      continue;
+    }
+
+    let value = match (supplied.remove(&arg.key), default) {
+      (Some(value), _) => arg.validate(value)?,
+      (None, Some(default)) if no_input => arg.validate(default.into())?,
+      (None, _) if no_input => {
+        return Err(
+          ProplateError::create(ProplateErrorKind::Arg {
+            key: arg.key.clone(),
+            kind: ArgErrorKind::Missing,
+          })
+          .with_ctx("cli::create::prompt_args")
+          .with_cause("Supply it with '--arg' or '--answers', or drop '--no-input'"),
+        )
+      }
+      (None, default) => Input::from(arg).with_default(default).prompt(),
+    };
+    ctx.insert(arg.key.clone(), value);
+  }
+
+  // keep the undeclared ones, they can still be bound in dynamic files
+  ctx.extend(supplied);
+
+  Ok(ctx)
+}
+
+/// Merges the answers file with "--arg", the latter taking precedence
+fn supplied_args(options: &CreateOptions) -> ProplateResult<Context> {
+  let mut ctx = match &options.answers {
+    Some(path) => load_answers(Path::new(path))?,
+    None => Context::new(),
+  };
+
+  for pair in &options.args {
+    let (key, value) = pair.split_once('=').ok_or_else(|| {
+      ProplateError::create(ProplateErrorKind::Arg {
+        key: pair.clone(),
+        kind: ArgErrorKind::Malformed,
+      })
+      .with_ctx("cli::create::supplied_args")
+    })?;
+    ctx.insert(key.trim().to_string(), value.into());
   }
 
   Ok(ctx)
@@ -93,3 +394,32 @@ fn do_init_git_repo(path: &Path) -> ProplateResult<()> {
   )?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{civil_from_days, parse_date};
+
+  #[test]
+  fn civil_dates() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    assert_eq!(civil_from_days(20088), (2024, 12, 31));
+    assert_eq!(civil_from_days(47541), (2100, 3, 1));
+  }
+
+  #[test]
+  fn dates() {
+    assert_eq!(parse_date("2024-01-31"), Some((2024, 1, 31)));
+    assert_eq!(parse_date("2024-02-29"), Some((2024, 2, 29)));
+    assert_eq!(parse_date("2024-01-31T10:00:00Z"), Some((2024, 1, 31)));
+
+    assert_eq!(parse_date("2024-02-31"), None);
+    assert_eq!(parse_date("2023-02-29"), None);
+    assert_eq!(parse_date("2100-02-29"), None);
+    assert_eq!(parse_date("2024-04-31"), None);
+    assert_eq!(parse_date("2024-01-01zzz"), None);
+    assert_eq!(parse_date("2024-01-01T"), None);
+    assert_eq!(parse_date("31/01/2024"), None);
+  }
+}
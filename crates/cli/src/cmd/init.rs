@@ -1,7 +1,9 @@
+use std::path::Path;
+
 use proplate_core::{
-  gen::bootstrap::{bootstrap, Context},
+  gen::bootstrap::{bootstrap, BootstrapOptions, ConflictPolicy, Context},
   local::get_local_template,
-  template::resolver::clone_template,
+  template::{config::link_schema, resolver::clone_template},
 };
 use proplate_errors::ProplateResult;
 use proplate_tui::logger;
@@ -25,9 +27,15 @@ pub fn init(id: String, dest: Option<String>) -> ProplateResult<String> {
   println!("{}", logger::title("Initializing template"));
 
   let mut template = clone_template(&to_clone, &dest)?;
-  let ctx = Context::from([("id".to_string(), id)]);
+  let ctx = Context::from([("id".to_string(), id.into())]);
 
-  bootstrap(&mut template, &dest, &ctx)?;
+  // init has always written over whatever is in dest
+  let options = BootstrapOptions {
+    on_conflict: ConflictPolicy::Overwrite,
+    ..Default::default()
+  };
+  bootstrap(&mut template, &dest, &ctx, &options)?;
+  link_schema(Path::new(&dest))?;
 
   Ok("".to_string())
 }
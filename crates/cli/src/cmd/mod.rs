@@ -1,2 +1,4 @@
 pub mod create;
 pub mod init;
+pub mod update;
+pub mod validate;
@@ -0,0 +1,108 @@
+use std::{
+  env::current_dir,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use proplate_core::gen::{
+  answers::{AnswersRecord, ANSWERS_FILE},
+  bootstrap::{bootstrap, BootstrapOptions},
+  update::{merge_update, UpdateReport},
+};
+use proplate_core::template::resolver::clone_template_at;
+use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult};
+use proplate_tui::logger;
+use uuid::Uuid;
+
+use super::create::prompt_args;
+
+#[derive(Debug, Default)]
+pub struct UpdateOptions {
+  /// Template commit to update to, the latest one if not set
+  pub to: Option<String>,
+  /// Fail instead of prompting for args introduced by the new template version
+  pub no_input: bool,
+}
+
+/// Re-applies a newer version of the template onto a project generated with "--record"
+pub fn update(project: &str, options: UpdateOptions) -> ProplateResult<()> {
+  let project = Path::new(project);
+  let record_path = project.join(ANSWERS_FILE);
+  let record = AnswersRecord::load(&record_path)?;
+
+  let commit = record.commit.clone().ok_or_else(|| {
+    ProplateError::create(ProplateErrorKind::Answers {
+      location: record_path.display().to_string(),
+    })
+    .with_ctx("cli::update")
+    .with_cause("The template wasn't a git repo, its previous version cannot be regenerated")
+  })?;
+
+  let tmp = current_dir()
+    .unwrap()
+    .join(".temp")
+    .join(format!("update-{}", Uuid::new_v4()));
+
+  let result = (|| -> ProplateResult<()> {
+    println!("{}", logger::title("Generating the previous version"));
+    generate(&record, Some(&commit), &tmp.join("old"), true)?;
+
+    println!("{}", logger::title("Generating the new version"));
+    let new_record = generate(
+      &record,
+      options.to.as_deref(),
+      &tmp.join("new"),
+      options.no_input,
+    )?;
+
+    println!("{}", logger::title("Merging"));
+    let report = merge_update(project, &tmp.join("old"), &tmp.join("new"))?;
+    new_record.write(project)?;
+    print_report(&report);
+    Ok(())
+  })();
+
+  if fs::remove_dir_all(&tmp).is_err() {
+    println!("{}", logger::warn("Unable to cleanup"));
+  }
+
+  result
+}
+
+/// Generates the recorded template at the given commit into `dest`
+fn generate(
+  record: &AnswersRecord,
+  commit: Option<&str>,
+  dest: &Path,
+  no_input: bool,
+) -> ProplateResult<AnswersRecord> {
+  let dest = dest.display().to_string();
+  let mut fork = clone_template_at(&record.fork_source, &dest, commit)?;
+  let ctx = prompt_args(&fork, record.context(), no_input)?;
+  bootstrap(&mut fork, &dest, &ctx, &BootstrapOptions::default())?;
+  Ok(AnswersRecord::new(&fork, &ctx))
+}
+
+fn print_report(report: &UpdateReport) {
+  let sections: [(&str, &Vec<PathBuf>); 6] = [
+    ("Added", &report.added),
+    ("Updated", &report.updated),
+    ("Removed", &report.removed),
+    ("Conflicts (resolve the markers)", &report.conflicts),
+    ("Rejected (see the .rej files)", &report.rejected),
+    (
+      "Kept (removed from the template but modified)",
+      &report.kept,
+    ),
+  ];
+
+  for (title, files) in sections {
+    if files.is_empty() {
+      continue;
+    }
+    println!("{}", logger::title(title));
+    for file in files {
+      println!("  {}", file.display());
+    }
+  }
+}
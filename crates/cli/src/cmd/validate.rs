@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use proplate_core::template::lint::{lint, Severity};
+use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult, TemplateErrorKind};
+use proplate_tui::logger;
+
+/// Prints the diagnostics of the template at `path`, failing if any of them is an error
+pub fn validate(path: &str) -> ProplateResult<()> {
+  println!("{}", logger::title("Validating template"));
+
+  let diagnostics = lint(Path::new(path));
+  for diagnostic in &diagnostics {
+    let line = diagnostic.to_string();
+    match diagnostic.severity {
+      Severity::Error => println!("{}", logger::error(&line)),
+      Severity::Warning => println!("{}", logger::warn(&line)),
+    }
+  }
+
+  let errors = diagnostics
+    .iter()
+    .filter(|d| d.severity == Severity::Error)
+    .count();
+  if errors > 0 {
+    return Err(
+      ProplateError::create(ProplateErrorKind::Template {
+        kind: TemplateErrorKind::Diagnostics { errors },
+        location: path.into(),
+      })
+      .with_ctx("cli::validate"),
+    );
+  }
+
+  println!(
+    "{}",
+    logger::success(&format!(
+      "Template is valid ({} warning(s))",
+      diagnostics.len()
+    ))
+  );
+  Ok(())
+}
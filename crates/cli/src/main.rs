@@ -1,8 +1,13 @@
-use clap::{arg, Command};
+use std::process::exit;
+
+use clap::{arg, ArgAction, Command};
 use cmd::{
-  create::{create, CreateOptions},
+  create::{create, replay, CreateOptions},
   init::init,
+  update::{update, UpdateOptions},
+  validate::validate,
 };
+use proplate_core::template::config::json_schema;
 use proplate_tui::logger::AsError;
 
 mod cmd;
@@ -29,14 +34,37 @@ Any Project starter in one tool"#,
         .about("create project from template")
         .args(&[
           arg!(--template <template> "Template location to start from (dir or github repo url)")
-            .required(true),
+            .required_unless_present("replay"),
           arg!(--dest <dest> "Destination path").required(true),
           arg!(--git  "Initialize git repo"),
+          arg!(--arg <arg> "Arg value as 'key=value', can be repeated").action(ArgAction::Append),
+          arg!(--answers <file> "Json or yaml file holding the args values"),
+          arg!(--"no-input" "Fail instead of prompting for args without value"),
+          arg!(--record "Write the answers record (.proplate-answers.json) in the project"),
+          arg!(--replay <file> "Generate the project recorded in an answers record, without prompting")
+            .conflicts_with_all(["template", "arg", "answers"]),
+          arg!(--"dry-run" "Print the files that would be generated without writing them"),
+          arg!(--"on-conflict" <policy> "What to do with the files that already exist in dest")
+            .value_parser(["abort", "skip", "overwrite", "prompt"])
+            .default_value("abort"),
+          arg!(--strict "Fail when a var of the dynamic files cannot be bound"),
+          arg!(--now <date> "Date used by the builtin vars instead of today's, e.g 2024-01-31"),
         ]),
       Command::new("init").about("initialize a template").args(&[
         arg!(<id> "id for the template").required(true),
         arg!(--dest <dest> "Destination path, if not set dir with 'id' will be created"),
       ]),
+      Command::new("validate")
+        .about("check a template without generating it")
+        .args(&[arg!(<path> "Template dir to check").required(true)]),
+      Command::new("schema").about("print the JSON Schema of the template config (meta.json)"),
+      Command::new("update")
+        .about("re-apply the latest template version onto a project created with --record")
+        .args(&[
+          arg!([path] "Project to update, defaults to the current dir"),
+          arg!(--to <commit> "Template commit to update to, defaults to the latest"),
+          arg!(--"no-input" "Fail instead of prompting for args without value"),
+        ]),
     ])
 }
 
@@ -47,23 +75,65 @@ fn main() -> Result<(), clap::Error> {
   match subcommands {
     Some(cmd) => match cmd {
       ("create", args) => {
-        let template_id = args.get_one::<String>("template").unwrap().as_str();
         let dest = args.get_one::<String>("dest").unwrap().as_str();
 
         let options = CreateOptions {
           git: args.get_flag("git"),
-          ..Default::default()
+          args: args
+            .get_many::<String>("arg")
+            .map(|a| a.cloned().collect())
+            .unwrap_or_default(),
+          answers: args.get_one::<String>("answers").cloned(),
+          no_input: args.get_flag("no-input"),
+          record: args.get_flag("record"),
+          dry_run: args.get_flag("dry-run"),
+          on_conflict: args
+            .get_one::<String>("on-conflict")
+            .unwrap()
+            .parse()
+            .unwrap(),
+          strict: args.get_flag("strict"),
+          now: args.get_one::<String>("now").cloned(),
         };
 
-        if let Err(e) = create(template_id, dest, options) {
-          eprint!("{}", e.print_err())
+        let result = match args.get_one::<String>("replay") {
+          Some(record) => replay(record, dest, options),
+          None => {
+            let template_id = args.get_one::<String>("template").unwrap();
+            create(template_id, dest, options)
+          }
+        };
+
+        if let Err(e) = result {
+          eprint!("{}", e.print_err());
+          exit(1);
         }
       }
       ("init", args) => {
         let id = args.get_one::<String>("id").unwrap();
         let dest = args.get_one::<String>("dest");
         init(String::from(id), dest.map(|e| e.into()))
-          .expect(format!("Unable to initialize template [id={}]", id,).as_str());
+          .unwrap_or_else(|_| panic!("Unable to initialize template [id={}]", id));
+      }
+      ("validate", args) => {
+        let path = args.get_one::<String>("path").unwrap();
+        if let Err(e) = validate(path) {
+          eprint!("{}", e.print_err());
+          exit(1);
+        }
+      }
+      ("schema", _) => println!("{}", json_schema()),
+      ("update", args) => {
+        let path = args.get_one::<String>("path").map_or(".", |p| p.as_str());
+        let options = UpdateOptions {
+          to: args.get_one::<String>("to").cloned(),
+          no_input: args.get_flag("no-input"),
+        };
+
+        if let Err(e) = update(path, options) {
+          eprint!("{}", e.print_err());
+          exit(1);
+        }
       }
       _ => {}
     },
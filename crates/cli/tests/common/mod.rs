@@ -1,5 +1,65 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+};
+
 use assert_cmd::Command;
+use uuid::Uuid;
 
 pub fn proplate_cli() -> Command {
   Command::cargo_bin("proplate").unwrap()
 }
+
+fn workspace_dir() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("../..")
+    .canonicalize()
+    .unwrap()
+}
+
+pub fn get_fixture(pkg: &str, name: &str) -> String {
+  workspace_dir()
+    .join("fixtures")
+    .join(pkg)
+    .join(name)
+    .display()
+    .to_string()
+}
+
+/// New temporary dir under the workspace "test_trash"
+pub fn new_trash() -> PathBuf {
+  workspace_dir()
+    .join("test_trash")
+    .join(Uuid::new_v4().to_string())
+}
+
+/// Both dirs must hold the same files with the same content
+#[allow(unused)]
+pub fn assert_dir_eq(dir1: &Path, dir2: &Path) {
+  let files = |dir: &Path| {
+    let mut files = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(path) = stack.pop() {
+      for entry in fs::read_dir(&path).unwrap() {
+        let path = entry.unwrap().path();
+        match path.is_dir() {
+          true => stack.push(path),
+          false => files.push(path.strip_prefix(dir).unwrap().to_path_buf()),
+        }
+      }
+    }
+    files.sort();
+    files
+  };
+
+  let (a, b) = (files(dir1), files(dir2));
+  assert_eq!(a, b);
+  for file in a {
+    assert_eq!(
+      fs::read_to_string(dir1.join(&file)).unwrap(),
+      fs::read_to_string(dir2.join(&file)).unwrap(),
+      "{} differs",
+      file.display()
+    );
+  }
+}
@@ -1,5 +1,7 @@
 mod common;
 
+use std::{fs, path::Path};
+
 #[test]
 fn check_cli() {
   let mut cmd = common::proplate_cli();
@@ -8,3 +10,932 @@ fn check_cli() {
     .assert()
     .stdout(format!("proplate {}\n", env!("CARGO_PKG_VERSION")));
 }
+
+#[test]
+fn schema() {
+  let output = common::proplate_cli()
+    .arg("schema")
+    .assert()
+    .success()
+    .get_output()
+    .stdout
+    .clone();
+  let stdout = String::from_utf8(output).unwrap();
+
+  assert!(stdout.contains("\"title\": \"TemplateConf\""));
+  assert!(stdout.contains("\"q_type\""));
+}
+
+mod non_interactive {
+  use super::*;
+
+  #[test]
+  fn with_args() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "author_name=Proplate"])
+      .args(["--arg", "license=MIT"])
+      .assert()
+      .success();
+
+    let snap = common::get_fixture("additional_ops", "mit-license-snapshot");
+    common::assert_dir_eq(Path::new(&snap), &dest);
+  }
+
+  #[test]
+  fn with_answers_file() {
+    let dest = common::new_trash();
+    let answers = dest.with_extension("yaml");
+    fs::create_dir_all(answers.parent().unwrap()).unwrap();
+    fs::write(
+      &answers,
+      "project_name: bsd-2-clause\nauthor_name: Proplate\nlicense: MIT\n",
+    )
+    .unwrap();
+
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .arg("--answers")
+      .arg(&answers)
+      // "--arg" wins over the answers file
+      .args(["--arg", "license=BSD-2-Clause"])
+      .assert()
+      .success();
+
+    let snap = common::get_fixture("additional_ops", "bsd-2-clause-license-snapshot");
+    common::assert_dir_eq(Path::new(&snap), &dest);
+  }
+
+  #[test]
+  fn with_structured_answers() {
+    let dest = common::new_trash();
+    let answers = dest.with_extension("json");
+    fs::create_dir_all(answers.parent().unwrap()).unwrap();
+    fs::write(
+      &answers,
+      r#"{
+        "name": "typed",
+        "docker": true,
+        "db": { "port": 5432 },
+        "services": [{ "name": "api", "replicas": 2 }, { "name": "worker", "replicas": 1 }]
+      }"#,
+    )
+    .unwrap();
+
+    common::proplate_cli()
+      .args(["create", "--no-input", "--record", "--template"])
+      .arg(common::get_fixture("dynamic_files", "typed"))
+      .arg("--dest")
+      .arg(&dest)
+      .arg("--answers")
+      .arg(&answers)
+      .assert()
+      .success();
+
+    let snap = common::get_fixture("dynamic_files", "typed-snapshot");
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      fs::read_to_string(Path::new(&snap).join("README.md")).unwrap()
+    );
+    // recorded with their type
+    let record = fs::read_to_string(dest.join(".proplate-answers.json")).unwrap();
+    assert!(record.contains("\"docker\": true"));
+    assert!(record.contains("\"port\": 5432"));
+  }
+
+  #[test]
+  fn float_answer() {
+    let dest = common::new_trash();
+    let answers = dest.with_extension("yaml");
+    fs::create_dir_all(answers.parent().unwrap()).unwrap();
+    fs::write(
+      &answers,
+      "name: select-both\nver: 1.0\nfile_structure: commonjs\n",
+    )
+    .unwrap();
+
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("dynamic_files", "select-both"))
+      .arg("--dest")
+      .arg(&dest)
+      .arg("--answers")
+      .arg(&answers)
+      .assert()
+      .success();
+
+    let package = fs::read_to_string(dest.join("package.json")).unwrap();
+    assert!(package.contains(r#""version": "1.0","#));
+  }
+
+  #[test]
+  fn null_answer() {
+    let dest = common::new_trash();
+    let answers = dest.with_extension("json");
+    fs::create_dir_all(answers.parent().unwrap()).unwrap();
+    fs::write(&answers, r#"{ "name": "select-both", "ver": null }"#).unwrap();
+
+    let output = common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("dynamic_files", "select-both"))
+      .arg("--dest")
+      .arg(&dest)
+      .arg("--answers")
+      .arg(&answers)
+      .assert()
+      .failure()
+      .get_output()
+      .stderr
+      .clone();
+
+    assert!(String::from_utf8(output)
+      .unwrap()
+      .contains("value 'null' is not valid for arg 'ver'"));
+  }
+
+  #[test]
+  fn missing_arg() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "license=MIT"])
+      .assert()
+      .failure();
+
+    assert!(!dest.exists());
+  }
+
+  #[test]
+  fn invalid_select_value() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "author_name=Proplate"])
+      .args(["--arg", "license=GPL"])
+      .assert()
+      .failure();
+
+    assert!(!dest.exists());
+  }
+
+  #[test]
+  fn confirm_default() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "confirm-docker"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=docker"])
+      .assert()
+      .success();
+
+    assert!(!dest.join("Dockerfile").exists());
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# docker\n"
+    );
+  }
+
+  #[test]
+  fn invalid_confirm_value() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "confirm-docker"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=docker", "--arg", "docker=yes"])
+      .assert()
+      .failure();
+
+    assert!(!dest.exists());
+  }
+
+  #[test]
+  fn multi_select() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--record", "--template"])
+      .arg(common::get_fixture("additional_ops", "multi-select-tools"))
+      .arg("--dest")
+      .arg(&dest)
+      .args([
+        "--arg",
+        "project_name=tools",
+        "--arg",
+        "tools=eslint,prettier",
+      ])
+      .assert()
+      .success();
+
+    let snap = common::get_fixture("additional_ops", "tools-snapshot");
+    assert!(dest.join(".prettierrc").exists());
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      fs::read_to_string(Path::new(&snap).join("README.md")).unwrap()
+    );
+    let record = fs::read_to_string(dest.join(".proplate-answers.json")).unwrap();
+    assert!(record.contains("\"tools\": [\n      \"eslint\",\n      \"prettier\"\n    ]"));
+  }
+
+  #[test]
+  fn invalid_multi_select_value() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "multi-select-tools"))
+      .arg("--dest")
+      .arg(&dest)
+      .args([
+        "--arg",
+        "project_name=tools",
+        "--arg",
+        "tools=eslint,tslint",
+      ])
+      .assert()
+      .failure();
+
+    assert!(!dest.exists());
+  }
+}
+
+mod arg_rules {
+  use super::*;
+
+  fn create(dest: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = common::proplate_cli();
+    cmd
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("args", "validated"))
+      .arg("--dest")
+      .arg(dest);
+    for arg in args {
+      cmd.args(["--arg", arg]);
+    }
+    cmd.assert()
+  }
+
+  #[test]
+  fn valid() {
+    let dest = common::new_trash();
+    create(&dest, &["package_name=my-pkg"]).success();
+
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# my-pkg\n\nListening on 8080\n"
+    );
+  }
+
+  #[test]
+  fn pattern_with_error_message() {
+    let dest = common::new_trash();
+    let output = create(&dest, &["package_name=My Pkg!"])
+      .failure()
+      .get_output()
+      .stderr
+      .clone();
+
+    assert!(String::from_utf8(output)
+      .unwrap()
+      .contains("Use lowercase letters, digits and dashes"));
+    assert!(!dest.exists());
+  }
+
+  #[test]
+  fn required() {
+    let dest = common::new_trash();
+    create(&dest, &["package_name="]).failure();
+    assert!(!dest.exists());
+  }
+
+  #[test]
+  fn number_bounds() {
+    let dest = common::new_trash();
+    let output = create(&dest, &["package_name=my-pkg", "port=70000"])
+      .failure()
+      .get_output()
+      .stderr
+      .clone();
+
+    assert!(String::from_utf8(output)
+      .unwrap()
+      .contains("Must be at most 65535"));
+  }
+
+  #[test]
+  fn min_length() {
+    let dest = common::new_trash();
+    let output = create(&dest, &["package_name=my-pkg", "description=short"])
+      .failure()
+      .get_output()
+      .stderr
+      .clone();
+
+    assert!(String::from_utf8(output)
+      .unwrap()
+      .contains("Must be at least 10 chars long"));
+  }
+}
+
+mod conditional_args {
+  use super::*;
+
+  #[test]
+  fn skipped() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--record", "--template"])
+      .arg(common::get_fixture("args", "conditional"))
+      .arg("--dest")
+      .arg(&dest)
+      // would be missing if it was asked
+      .assert()
+      .success();
+
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# Conditional\n"
+    );
+    let record = fs::read_to_string(dest.join(".proplate-answers.json")).unwrap();
+    assert!(!record.contains("ci_provider"));
+  }
+
+  #[test]
+  fn asked() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("args", "conditional"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "use_ci=true", "--arg", "ci_provider=gitlab"])
+      .assert()
+      .success();
+
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# Conditional\n\nBuilt on gitlab\n"
+    );
+  }
+
+  #[test]
+  fn supplied_but_skipped() {
+    let dest = common::new_trash();
+    let output = common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("args", "conditional"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "ci_provider=gitlab"])
+      .assert()
+      .success()
+      .get_output()
+      .stdout
+      .clone();
+
+    assert!(String::from_utf8(output).unwrap().contains(
+      "'ci_provider' is not asked as its \"when\" conditions don't hold, 'gitlab' is ignored"
+    ));
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# Conditional\n"
+    );
+  }
+
+  #[test]
+  fn relevant_from_builtins() {
+    let dest = common::new_trash().join("with-docs");
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("args", "conditional"))
+      .arg("--dest")
+      .arg(&dest)
+      // "docs_url" is asked because of "_dest_name"
+      .assert()
+      .failure();
+  }
+
+  #[test]
+  fn missing_when_relevant() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("args", "conditional"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "use_ci=true"])
+      .assert()
+      .failure();
+  }
+}
+
+mod builtins {
+  use super::*;
+
+  #[test]
+  fn bound() {
+    let dest = common::new_trash().join("my-dest");
+    let gitconfig = dest.with_extension("gitconfig");
+    fs::create_dir_all(gitconfig.parent().unwrap()).unwrap();
+    fs::write(
+      &gitconfig,
+      "[user]\n\tname = Proplate\n\temail = proplate@example.com\n",
+    )
+    .unwrap();
+
+    common::proplate_cli()
+      .env("GIT_CONFIG_GLOBAL", &gitconfig)
+      .env("GIT_CONFIG_NOSYSTEM", "1")
+      // ignore the config of the repo the tests run in
+      .env("GIT_DIR", dest.with_extension("git"))
+      .args([
+        "create",
+        "--no-input",
+        "--record",
+        "--now",
+        "2024-01-31",
+        "--template",
+      ])
+      .arg(common::get_fixture("args", "builtins"))
+      .arg("--dest")
+      .arg(&dest)
+      .assert()
+      .success();
+
+    assert_eq!(
+      fs::read_to_string(dest.join("LICENSE")).unwrap(),
+      "Copyright (c) 2024 Proplate <proplate@example.com>\n\n\
+       Generated on 2024-01-31 from builtins into my-dest\n"
+    );
+    let record = fs::read_to_string(dest.join(".proplate-answers.json")).unwrap();
+    assert!(record.contains("\"_uuid\""));
+  }
+
+  #[test]
+  fn invalid_now() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--now", "31/01/2024", "--template"])
+      .arg(common::get_fixture("args", "builtins"))
+      .arg("--dest")
+      .arg(&dest)
+      .assert()
+      .failure();
+
+    assert!(!dest.exists());
+  }
+}
+
+mod interpolated_defaults {
+  use super::*;
+
+  fn create(dest: &Path) -> assert_cmd::Command {
+    let mut cmd = common::proplate_cli();
+    cmd
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("args", "defaults"))
+      .arg("--dest")
+      .arg(dest)
+      .args(["--arg", "project_name=proplate"]);
+    cmd
+  }
+
+  #[test]
+  fn from_answers_and_env() {
+    let dest = common::new_trash();
+    create(&dest)
+      .env("PROPLATE_TEST_AUTHOR", "Yume")
+      .assert()
+      .success();
+
+    // env vars are only bound in default values, the file keeps the braced one as is
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# proplate-cli\n\nBy Yume [${env:PROPLATE_TEST_AUTHOR}]\n"
+    );
+  }
+
+  #[test]
+  fn env_fallback() {
+    let dest = common::new_trash();
+    create(&dest)
+      .env_remove("PROPLATE_TEST_AUTHOR")
+      .assert()
+      .success();
+
+    assert_eq!(
+      fs::read_to_string(dest.join("README.md")).unwrap(),
+      "# proplate-cli\n\nBy nobody [${env:PROPLATE_TEST_AUTHOR}]\n"
+    );
+  }
+}
+
+mod answers_record {
+  use super::*;
+
+  #[test]
+  fn record_then_replay() {
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--no-input", "--record", "--template"])
+      .arg(common::get_fixture("dynamic_files", "select-both"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "name=select-both", "--arg", "ver=1.0.0"])
+      .args(["--arg", "file_structure=commonjs"])
+      .assert()
+      .success();
+
+    let record = dest.join(".proplate-answers.json");
+    let content = fs::read_to_string(&record).unwrap();
+    assert!(content.contains(r#""template_id": "select-both""#));
+    assert!(content.contains(r#""file_structure": "commonjs""#));
+    assert!(content.contains(r#""commit": null"#));
+
+    let replayed = common::new_trash();
+    common::proplate_cli()
+      .args(["create", "--record", "--replay"])
+      .arg(&record)
+      .arg("--dest")
+      .arg(&replayed)
+      .assert()
+      .success();
+
+    common::assert_dir_eq(&dest, &replayed);
+  }
+
+  #[test]
+  fn replay_from_another_dir() {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let dest = common::new_trash();
+    common::proplate_cli()
+      .current_dir(&workspace)
+      .args(["create", "--no-input", "--record", "--template"])
+      .arg("fixtures/dynamic_files/select-both")
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "name=select-both", "--arg", "ver=1.0.0"])
+      .args(["--arg", "file_structure=commonjs"])
+      .assert()
+      .success();
+
+    let elsewhere = common::new_trash();
+    fs::create_dir_all(&elsewhere).unwrap();
+    let replayed = common::new_trash();
+    common::proplate_cli()
+      .current_dir(&elsewhere)
+      .args(["create", "--record", "--replay"])
+      .arg(dest.join(".proplate-answers.json"))
+      .arg("--dest")
+      .arg(&replayed)
+      .assert()
+      .success();
+
+    common::assert_dir_eq(&dest, &replayed);
+  }
+
+  #[test]
+  fn replay_conflicts_with_template() {
+    common::proplate_cli()
+      .args([
+        "create",
+        "--replay",
+        "answers.json",
+        "--dest",
+        "out",
+        "--template",
+      ])
+      .arg(common::get_fixture("dynamic_files", "select-both"))
+      .assert()
+      .failure();
+  }
+}
+
+mod update {
+  use super::*;
+  use std::process::Command;
+
+  fn git(args: &[&str], dir: &Path) {
+    let status = Command::new("git")
+      .args(["-c", "user.name=proplate", "-c", "user.email=proplate@test"])
+      .args(args)
+      .current_dir(dir)
+      .output()
+      .unwrap()
+      .status;
+    assert!(status.success());
+  }
+
+  #[test]
+  fn merge_newer_template() {
+    let root = common::new_trash();
+    let (template, project) = (root.join("template"), root.join("project"));
+
+    fs::create_dir_all(&template).unwrap();
+    fs::write(
+      template.join("meta.json"),
+      r#"{ "id": "updatable", "args": [{ "key": "name", "q_type": "Text", "label": "Name" }] }"#,
+    )
+    .unwrap();
+    fs::write(template.join("README.md"), "# $name\n\nbody\n").unwrap();
+    git(&["init", "--quiet"], &template);
+    git(&["add", "-A"], &template);
+    git(&["commit", "--quiet", "-m", "v1"], &template);
+
+    common::proplate_cli()
+      .args([
+        "create",
+        "--no-input",
+        "--record",
+        "--arg",
+        "name=app",
+        "--template",
+      ])
+      .arg(&template)
+      .arg("--dest")
+      .arg(&project)
+      .assert()
+      .success();
+
+    // both the user and the template move on
+    fs::write(project.join("README.md"), "# app (mine)\n\nbody\n").unwrap();
+    fs::write(template.join("README.md"), "# $name\n\nbody\n\nfooter\n").unwrap();
+    fs::write(template.join("NEW"), "$name\n").unwrap();
+    git(&["add", "-A"], &template);
+    git(&["commit", "--quiet", "-m", "v2"], &template);
+
+    common::proplate_cli()
+      .args(["update", "--no-input"])
+      .arg(&project)
+      .assert()
+      .success();
+
+    assert_eq!(
+      fs::read_to_string(project.join("README.md")).unwrap(),
+      "# app (mine)\n\nbody\n\nfooter\n"
+    );
+    assert_eq!(fs::read_to_string(project.join("NEW")).unwrap(), "app\n");
+  }
+
+  #[test]
+  fn without_record() {
+    common::proplate_cli()
+      .args(["update"])
+      .arg(common::new_trash())
+      .assert()
+      .failure();
+  }
+}
+
+mod strict {
+  use super::*;
+
+  #[test]
+  fn unresolved_vars() {
+    let dest = common::new_trash();
+    let output = common::proplate_cli()
+      .args(["create", "--no-input", "--strict", "--template"])
+      .arg(common::get_fixture("dynamic_files", "braced"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "name=proplate", "--arg", "padded=x"])
+      .assert()
+      .failure()
+      .get_output()
+      .stderr
+      .clone();
+    let stderr = String::from_utf8(output).unwrap();
+
+    assert!(stderr.contains("README.md:17:1: ${name | unknown_filter}"));
+    assert!(!Path::new(&dest).exists());
+  }
+}
+
+mod dry_run {
+  use super::*;
+
+  #[test]
+  fn preview() {
+    let dest = common::new_trash();
+    fs::create_dir_all(&dest).unwrap();
+    fs::write(dest.join("main.js"), "").unwrap();
+
+    let output = common::proplate_cli()
+      .args([
+        "create",
+        "--no-input",
+        "--dry-run",
+        "--on-conflict",
+        "overwrite",
+      ])
+      .arg("--template")
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "author_name=Proplate"])
+      .args(["--arg", "license=MIT"])
+      .assert()
+      .success()
+      .get_output()
+      .stdout
+      .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("Copy .proplate_aux_utils/MIT -> LICENSE"));
+    assert!(stdout.contains("+ LICENSE"));
+    assert!(stdout.contains("+ package.json"));
+    assert!(stdout.contains("~ main.js"));
+
+    // untouched
+    assert_eq!(fs::read_dir(&dest).unwrap().count(), 1);
+    assert_eq!(fs::read_to_string(dest.join("main.js")).unwrap(), "");
+  }
+
+  #[test]
+  fn preview_skip() {
+    let dest = common::new_trash();
+    fs::create_dir_all(&dest).unwrap();
+    fs::write(dest.join("main.js"), "").unwrap();
+
+    let output = common::proplate_cli()
+      .args(["create", "--no-input", "--dry-run", "--on-conflict", "skip"])
+      .arg("--template")
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "author_name=Proplate"])
+      .args(["--arg", "license=MIT"])
+      .assert()
+      .success()
+      .get_output()
+      .stdout
+      .clone();
+    let stdout = String::from_utf8(output).unwrap();
+
+    assert!(stdout.contains("= main.js"));
+    assert!(!stdout.contains("~ main.js"));
+  }
+
+  #[test]
+  fn preview_abort() {
+    let dest = common::new_trash();
+    fs::create_dir_all(&dest).unwrap();
+    fs::write(dest.join("main.js"), "").unwrap();
+
+    let output = common::proplate_cli()
+      .args(["create", "--no-input", "--dry-run", "--template"])
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "author_name=Proplate"])
+      .args(["--arg", "license=MIT"])
+      .assert()
+      .failure()
+      .get_output()
+      .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(stderr.contains("The destination is not empty"));
+    assert!(!stdout.contains("Files to overwrite"));
+  }
+}
+
+mod on_conflict {
+  use super::*;
+  use std::path::PathBuf;
+
+  fn create_into_non_empty(policy: Option<&str>) -> (PathBuf, bool) {
+    let dest = common::new_trash();
+    fs::create_dir_all(&dest).unwrap();
+    fs::write(dest.join("main.js"), "mine").unwrap();
+
+    let mut cmd = common::proplate_cli();
+    cmd
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("additional_ops", "conditional-license"))
+      .arg("--dest")
+      .arg(&dest)
+      .args(["--arg", "project_name=mit", "--arg", "author_name=Proplate"])
+      .args(["--arg", "license=MIT"]);
+    if let Some(policy) = policy {
+      cmd.args(["--on-conflict", policy]);
+    }
+
+    let success = cmd.output().unwrap().status.success();
+    (dest, success)
+  }
+
+  #[test]
+  fn abort_by_default() {
+    let (dest, success) = create_into_non_empty(None);
+    assert!(!success);
+    assert_eq!(fs::read_dir(&dest).unwrap().count(), 1);
+    assert_eq!(fs::read_to_string(dest.join("main.js")).unwrap(), "mine");
+  }
+
+  #[test]
+  fn skip() {
+    let (dest, success) = create_into_non_empty(Some("skip"));
+    assert!(success);
+    assert!(dest.join("LICENSE").exists());
+    assert_eq!(fs::read_to_string(dest.join("main.js")).unwrap(), "mine");
+  }
+
+  #[test]
+  fn overwrite() {
+    let (dest, success) = create_into_non_empty(Some("overwrite"));
+    assert!(success);
+    let snap = common::get_fixture("additional_ops", "mit-license-snapshot");
+    common::assert_dir_eq(Path::new(&snap), &dest);
+  }
+}
+
+mod manifests {
+  use super::*;
+
+  fn create_err(name: &str) -> String {
+    let dest = common::new_trash();
+    let output = common::proplate_cli()
+      .args(["create", "--no-input", "--template"])
+      .arg(common::get_fixture("manifests", name))
+      .arg("--dest")
+      .arg(&dest)
+      .assert()
+      .failure()
+      .get_output()
+      .stderr
+      .clone();
+    assert!(!Path::new(&dest).exists());
+    // local forks are made next to dest as "<dest>-<uuid>"
+    let fork_prefix = format!("{}-", dest.file_name().unwrap().to_str().unwrap());
+    let leaked = fs::read_dir(dest.parent().unwrap())
+      .unwrap()
+      .filter_map(|e| e.ok())
+      .any(|e| e.file_name().to_string_lossy().starts_with(&fork_prefix));
+    assert!(!leaked, "the template fork was not cleaned up");
+    String::from_utf8(output).unwrap()
+  }
+
+  #[test]
+  fn several() {
+    let stderr = create_err("several");
+    assert!(stderr.contains("Only one manifest is allowed, found meta.json, meta.yml"));
+  }
+
+  #[test]
+  fn malformed() {
+    let stderr = create_err("malformed");
+    assert!(stderr.contains("meta.toml:2:16: invalid basic string"));
+  }
+
+  #[test]
+  fn misspelled_rule() {
+    let stderr = create_err("misspelled-rule");
+    assert!(stderr.contains("meta.json:8:16: unknown field `requried`"));
+  }
+}
+
+mod validate {
+  use super::*;
+
+  #[test]
+  fn valid() {
+    common::proplate_cli()
+      .arg("validate")
+      .arg(common::get_fixture("lint", "valid"))
+      .assert()
+      .success();
+  }
+
+  #[test]
+  fn broken() {
+    let output = common::proplate_cli()
+      .arg("validate")
+      .arg(common::get_fixture("lint", "broken"))
+      .assert()
+      .failure()
+      .get_output()
+      .clone();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+
+    assert!(stdout.contains("error: Dynamic file 'missing.md' does not exist"));
+    assert!(stdout.contains("warning: 'unused' is never used"));
+    assert!(stderr.contains("has 7 error(s)"));
+  }
+}
@@ -1,5 +1,6 @@
 use std::{
   fs,
+  io::Write,
   path::{Path, PathBuf},
 };
 
@@ -13,7 +14,7 @@ pub fn copy_fdir(entry: &Path, dest: &Path, except: Option<Vec<PathBuf>>) -> std
   for (file, filename) in walk_dir_skip(entry, except.unwrap_or_default())? {
     let to = dest.join(&filename);
     if let Some(parent) = to.parent() {
-      fs::create_dir_all(&parent)?;
+      fs::create_dir_all(parent)?;
     }
     fs::copy(&file, &to)?;
   }
@@ -43,6 +44,39 @@ pub fn remove_fdir(path: &Path) -> std::io::Result<()> {
   Ok(())
 }
 
+/// Moves a file/dir, creating the missing parent dirs of `dest`
+pub fn move_fdir(path: &Path, dest: &Path) -> std::io::Result<()> {
+  if let Some(parent) = dest.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  if fs::rename(path, dest).is_ok() {
+    return Ok(());
+  }
+
+  // e.g "dest" is on another device
+  match path.is_dir() {
+    true => copy_fdir(path, dest, None)?,
+    false => {
+      fs::copy(path, dest)?;
+    }
+  }
+  remove_fdir(path)
+}
+
+/// Writes or appends `content` to the file, creating it along with its missing parent dirs
+pub fn write_file(path: &Path, content: &str, append: bool) -> std::io::Result<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  let mut file = fs::OpenOptions::new()
+    .create(true)
+    .write(true)
+    .append(append)
+    .truncate(!append)
+    .open(path)?;
+  file.write_all(content.as_bytes())
+}
+
 /// Updates the provided file content
 pub fn map_file(path: &Path, f: impl Fn(&str) -> String) -> std::io::Result<()> {
   let content = fs::read_to_string(path)?;
@@ -21,7 +21,7 @@ struct WalkDir {
 impl WalkDir {
   pub fn new(skip: Option<Vec<PathBuf>>) -> WalkDir {
     WalkDir {
-      skip: skip.unwrap_or_else(|| Vec::new()),
+      skip: skip.unwrap_or_default(),
     }
   }
 
@@ -31,7 +31,7 @@ impl WalkDir {
       return Ok(vec![self.walk_file(path, &dir.unwrap_or("".into()))]);
     }
     let mut dirs: Vec<WalkdirPathBuf> = Vec::new();
-    self.walk_dir(&path, &dir.unwrap_or("".into()), &mut dirs)?;
+    self.walk_dir(path, &dir.unwrap_or("".into()), &mut dirs)?;
 
     Ok(dirs)
   }
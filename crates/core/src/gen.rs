@@ -1 +1,6 @@
+pub mod answers;
 pub mod bootstrap;
+pub mod builtins;
+pub mod computed;
+pub mod update;
+pub mod value;
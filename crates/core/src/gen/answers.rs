@@ -0,0 +1,120 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use proplate_errors::{ArgErrorKind, ProplateError, ProplateErrorKind, ProplateResult};
+use serde::{Deserialize, Serialize};
+
+use super::{bootstrap::Context, value::Value};
+use crate::template::Template;
+
+/// Answers record written in the generated project
+pub const ANSWERS_FILE: &str = ".proplate-answers.json";
+
+/// Everything that is needed to generate the same project again
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AnswersRecord {
+  pub template_id: String,
+  pub fork_source: String,
+  /// Template commit, if the template is a git repo
+  pub commit: Option<String>,
+  pub answers: BTreeMap<String, Value>,
+  pub proplate_version: String,
+}
+
+impl AnswersRecord {
+  pub fn new(template: &Template, ctx: &Context) -> AnswersRecord {
+    AnswersRecord {
+      template_id: template.conf.id.clone(),
+      fork_source: absolute_source(&template.fork_source),
+      commit: template.commit.clone(),
+      answers: ctx.clone().into_iter().collect(),
+      proplate_version: env!("CARGO_PKG_VERSION").into(),
+    }
+  }
+
+  pub fn load(path: &Path) -> ProplateResult<AnswersRecord> {
+    let create_err = |cause: &str| {
+      ProplateError::create(ProplateErrorKind::Answers {
+        location: path.display().to_string(),
+      })
+      .with_ctx("gen:answers:record:load")
+      .with_cause(cause)
+    };
+    let content = fs::read_to_string(path).map_err(|e| create_err(&e.to_string()))?;
+    serde_json::from_str(&content).map_err(|e| create_err(&e.to_string()))
+  }
+
+  /// Writes the record as "ANSWERS_FILE" under `dest`
+  pub fn write(&self, dest: &Path) -> ProplateResult<()> {
+    let path = dest.join(ANSWERS_FILE);
+    let content = serde_json::to_string_pretty(self).expect("Serialize answers record");
+    fs::write(&path, content + "\n").map_err(|e| {
+      ProplateError::create(ProplateErrorKind::Fs {
+        concerned_paths: vec![path.display().to_string()],
+        operation: "write".into(),
+      })
+      .with_ctx("gen:answers:record:write")
+      .with_cause(&e.to_string())
+    })
+  }
+
+  pub fn context(&self) -> Context {
+    self.answers.clone().into_iter().collect()
+  }
+}
+
+/// Local template paths are recorded as absolute, so that the record can be replayed from any
+/// dir, git urls are kept as is
+fn absolute_source(source: &str) -> String {
+  Path::new(source)
+    .canonicalize()
+    .map(|path| path.display().to_string())
+    .unwrap_or_else(|_| source.into())
+}
+
+/// Reads a "key: value" answers file, either json or yaml (guessed from the extension)
+///
+/// # Example
+///
+/// answers.json
+/// ```json
+/// { "project_name": "proplate", "docker": true, "tools": ["eslint", "prettier"], "db": { "port": 5432 } }
+/// ```
+pub fn load_answers(path: &Path) -> ProplateResult<Context> {
+  let location = path.display().to_string();
+  let create_err = |cause: &str| {
+    ProplateError::create(ProplateErrorKind::Answers {
+      location: location.clone(),
+    })
+    .with_ctx("gen:answers:load")
+    .with_cause(cause)
+  };
+
+  let content = fs::read_to_string(path).map_err(|e| create_err(&e.to_string()))?;
+
+  // answers are read as optional so that a null one is reported along with its key
+  let answers: BTreeMap<String, Option<Value>> = match path.extension().and_then(|e| e.to_str()) {
+    Some("json") => serde_json::from_str(&content).map_err(|e| create_err(&e.to_string())),
+    Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| create_err(&e.to_string())),
+    _ => Err(create_err("Expected a '.json', '.yaml' or '.yml' file")),
+  }?;
+
+  answers
+    .into_iter()
+    .map(|(key, value)| match value {
+      Some(value) => Ok((key, value)),
+      None => Err(
+        ProplateError::create(ProplateErrorKind::Arg {
+          key,
+          kind: ArgErrorKind::InvalidValue {
+            value: "null".into(),
+          },
+        })
+        .with_ctx("gen:answers:load")
+        .with_cause(&format!(
+          "Remove it from '{}' to leave the arg without value",
+          location
+        )),
+      ),
+    })
+    .collect()
+}
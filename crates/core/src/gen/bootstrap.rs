@@ -1,41 +1,159 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+  collections::{BTreeSet, HashMap},
+  fs,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use diffy::DiffOptions;
+use inquire::Confirm;
 
+use super::{computed::compute, value::Value};
 use crate::{
-  fs as pfs,
-  template::{config::analyze_dyn_files, interpolation::Interpolate, op::Execute, Template},
+  fs::walk::{walk_dir_skip, WalkdirPathBuf},
+  template::{
+    config::analyze_dyn_files,
+    interpolation::{
+      block::{find_unresolved, render, SyntaxError},
+      find_unresolved as find_unresolved_in, interpolate,
+    },
+    op::Execute,
+    Template,
+  },
 };
 
-use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult};
+use proplate_errors::{
+  CliErrorKind, ProplateError, ProplateErrorKind, ProplateResult, TemplateErrorKind,
+};
 use proplate_tui::logger;
 
 /// typealias for template ctx
-pub type Context = HashMap<String, String>;
+pub type Context = HashMap<String, Value>;
+
+/// What to do with the files that already exist in the dest directory
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+  /// Refuse to generate into a non-empty dest
+  #[default]
+  Abort,
+  /// Keep the existing files
+  Skip,
+  Overwrite,
+  /// Ask for each file, showing the diff with the generated one
+  Prompt,
+}
+
+impl FromStr for ConflictPolicy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "abort" => Ok(ConflictPolicy::Abort),
+      "skip" => Ok(ConflictPolicy::Skip),
+      "overwrite" => Ok(ConflictPolicy::Overwrite),
+      "prompt" => Ok(ConflictPolicy::Prompt),
+      _ => Err(format!("Unknown conflict policy '{}'", s)),
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+pub struct BootstrapOptions {
+  pub on_conflict: ConflictPolicy,
+  /// Enforces strict mode even if the template doesn't enable it
+  pub strict: bool,
+}
+
+/// What `bootstrap` would have done
+#[derive(Debug, Default)]
+pub struct DryRunReport {
+  /// Executed additional operations
+  pub operations: Vec<String>,
+  pub created: Vec<PathBuf>,
+  pub overwritten: Vec<PathBuf>,
+  /// Existing files kept as is under the "skip" policy
+  pub skipped: Vec<PathBuf>,
+}
 
 /// Processes the given `template` using the `ctx` and outputs the result to `dest` directory
-pub fn bootstrap(template: &mut Template, dest: &str, ctx: &Context) -> ProplateResult<()> {
+pub fn bootstrap(
+  template: &mut Template,
+  dest: &str,
+  ctx: &Context,
+  options: &BootstrapOptions,
+) -> ProplateResult<()> {
   (|| -> ProplateResult<()> {
-    process_template(template, ctx)?;
-    prepare_dest(dest)?;
-    copy_files(template, dest)?;
+    let ctx = &compute(template, ctx)?;
+    process_template(template, ctx, options)?;
+    prepare_dest(dest, options.on_conflict)?;
+    copy_files(template, dest, ctx, options.on_conflict)?;
     cleanup(template)?;
     Ok(())
   })()
-  .map_err(|e| -> ProplateError {
-    if let Err(_) = cleanup(template) {
+  .inspect_err(|_| {
+    if cleanup(template).is_err() {
+      logger::warn("Unable to cleanup");
+    }
+  })
+}
+
+/// Same as `bootstrap` but reports what would be written instead of touching the `dest` directory
+pub fn dry_run(
+  template: &mut Template,
+  dest: &str,
+  ctx: &Context,
+  options: &BootstrapOptions,
+) -> ProplateResult<DryRunReport> {
+  (|| -> ProplateResult<DryRunReport> {
+    // the generation would abort
+    check_dest(dest, options.on_conflict)?;
+    let ctx = &compute(template, ctx)?;
+    let mut report = DryRunReport {
+      operations: process_template(template, ctx, options)?,
+      ..Default::default()
+    };
+
+    for (_, relative) in template_files(template, ctx)? {
+      match (
+        Path::new(dest).join(&relative).exists(),
+        options.on_conflict,
+      ) {
+        (false, _) => report.created.push(relative),
+        (true, ConflictPolicy::Skip) => report.skipped.push(relative),
+        (true, _) => report.overwritten.push(relative),
+      }
+    }
+
+    cleanup(template)?;
+    Ok(report)
+  })()
+  .inspect_err(|_| {
+    if cleanup(template).is_err() {
       logger::warn("Unable to cleanup");
     }
-    e
   })
 }
 
 /// Executes hook and bind ctx onto dynamic_files.
-pub fn process_template(template: &mut Template, ctx: &Context) -> ProplateResult<()> {
+/// Returns the description of the executed additional operations
+pub fn process_template(
+  template: &mut Template,
+  ctx: &Context,
+  options: &BootstrapOptions,
+) -> ProplateResult<Vec<String>> {
   println!("{}", logger::step("Running additional operations..."));
 
+  let mut executed = Vec::new();
+  let mut bound = Vec::new();
+
   // run "additional_operations" in order to process the dynamically
   // added file in the extra operation.
-  for op in &template.conf.additional_operations {
-    op.execute(&ctx)?;
+  for additional_op in &template.conf.additional_operations {
+    for op in additional_op.selected(ctx)? {
+      op.execute(ctx)?;
+      executed.push(op.describe(&template.base_path));
+      bound.extend(op.bound_file(ctx));
+    }
   }
 
   println!(
@@ -45,30 +163,148 @@ pub fn process_template(template: &mut Template, ctx: &Context) -> ProplateResul
   if template.conf.require_dyn_file_analysis {
     analyze_dyn_files(&mut template.conf, &template.base_path);
   }
+  // the written content is already bound
+  (template.conf.dynamic_files).retain(|f| !bound.iter().any(|file| Path::new(f) == file));
+
+  if options.strict || template.conf.strict {
+    println!(
+      "{}",
+      logger::step("Looking for vars that cannot be bound...")
+    );
+    check_unresolved(template, ctx)?;
+  }
 
   println!("{}", logger::step("Binding ctx to dynamic_files..."));
 
   for filepath in &template.conf.dynamic_files {
-    println!("      {}", logger::step(&format!("processing...")));
-    bind_ctx_to_file(Path::new(&filepath), ctx);
+    println!("      {}", logger::step("processing..."));
+    bind_ctx_to_file(template, Path::new(&filepath), ctx)?;
   }
 
-  Ok(())
+  Ok(executed)
+}
+
+/// Fails with every var of the dynamic files that cannot be bound
+fn check_unresolved(template: &Template, ctx: &Context) -> ProplateResult<()> {
+  let mut occurrences = Vec::new();
+  let declared = template.conf.declared_vars();
+
+  for filepath in &template.conf.dynamic_files {
+    let path = Path::new(filepath);
+    // same as binding, files that cannot be read are ignored
+    let Ok(source) = fs::read_to_string(path) else {
+      continue;
+    };
+    let relative = path.strip_prefix(&template.base_path).unwrap_or(path);
+
+    let found = find_unresolved(&source, ctx, &declared)
+      .map_err(|e| invalid_block(template, path, &source, &e))?;
+    for unresolved in found {
+      let (line, column) = unresolved.line_col(&source);
+      occurrences.push(format!(
+        "{}:{}:{}: {} ({})",
+        relative.display(),
+        line,
+        column,
+        unresolved.var,
+        unresolved.reason
+      ));
+    }
+  }
+
+  occurrences.extend(unresolved_in_paths(template, ctx)?);
+
+  match occurrences.is_empty() {
+    true => Ok(()),
+    false => Err(
+      ProplateError::create(ProplateErrorKind::Template {
+        kind: TemplateErrorKind::UnresolvedVars { occurrences },
+        location: template.fork_source.clone(),
+      })
+      .with_ctx("gen:bootstrap:check_unresolved"),
+    ),
+  }
 }
 
-/// Replaces dynamic var "$var" with their actual value
-pub fn bind_ctx_to_file(path: &Path, ctx: &Context) {
-  match pfs::map_file(path, |s| s.to_string().interpolate(ctx)) {
-    Err(_) => {
-      // TODO: warn if not found but wasn't removed in additional_op either
+/// Vars of the file and dir names that cannot be bound, reported once per templated dir
+fn unresolved_in_paths(template: &Template, ctx: &Context) -> ProplateResult<Vec<String>> {
+  let mut occurrences = BTreeSet::new();
+
+  for (_, relative) in template_paths(template)? {
+    let mut prefix = PathBuf::new();
+    for component in relative.iter() {
+      prefix.push(component);
+      for unresolved in find_unresolved_in(&component.to_string_lossy(), ctx) {
+        occurrences.insert(format!(
+          "{}: {} ({})",
+          prefix.display(),
+          unresolved.var,
+          unresolved.reason
+        ));
+      }
     }
-    _ => (),
+  }
+
+  Ok(occurrences.into_iter().collect())
+}
+
+/// Renders the blocks of a dynamic file and replaces its vars "$var" with their actual value,
+/// the blocks over vars the template doesn't declare being kept as is
+pub fn bind_ctx_to_file(template: &Template, path: &Path, ctx: &Context) -> ProplateResult<()> {
+  // TODO: warn if not found but wasn't removed in additional_op either
+  let Ok(source) = fs::read_to_string(path) else {
+    return Ok(());
+  };
+  let declared = template.conf.declared_vars();
+  let rendered =
+    render(&source, ctx, &declared).map_err(|e| invalid_block(template, path, &source, &e))?;
+  fs::write(path, rendered).map_err(|e| {
+    ProplateError::create(ProplateErrorKind::Fs {
+      concerned_paths: vec![path.display().to_string()],
+      operation: "write".into(),
+    })
+    .with_ctx("gen:bootstrap:bind_ctx_to_file")
+    .with_cause(&e.to_string())
+  })
+}
+
+fn invalid_block(template: &Template, path: &Path, source: &str, e: &SyntaxError) -> ProplateError {
+  let (line, column) = e.line_col(source);
+  let relative = path.strip_prefix(&template.base_path).unwrap_or(path);
+  ProplateError::create(ProplateErrorKind::Template {
+    kind: TemplateErrorKind::InvalidBlock {
+      occurrence: format!("{}:{}:{}: {}", relative.display(), line, column, e.reason),
+    },
+    location: template.fork_source.clone(),
+  })
+  .with_ctx("gen:bootstrap:bind_ctx_to_file")
+}
+
+/// Ensures that the project can be generated in `dest` under the given conflict policy
+pub fn check_dest(dest: &str, on_conflict: ConflictPolicy) -> ProplateResult<()> {
+  let is_empty = match fs::read_dir(dest) {
+    Ok(mut entries) => entries.next().is_none(),
+    // nothing there yet
+    Err(_) => true,
+  };
+
+  match is_empty || on_conflict != ConflictPolicy::Abort {
+    true => Ok(()),
+    false => Err(
+      ProplateError::create(ProplateErrorKind::Fs {
+        concerned_paths: vec![dest.into()],
+        operation: "check_dest".into(),
+      })
+      .with_ctx("gen:bootstrap:check_dest")
+      .with_cause("The destination is not empty, use another conflict policy to generate into it"),
+    ),
   }
 }
 
 /// Create project dest dir
-fn prepare_dest(dest: &str) -> ProplateResult<()> {
+fn prepare_dest(dest: &str, on_conflict: ConflictPolicy) -> ProplateResult<()> {
   println!("{}", logger::title("Finalizing"));
+  check_dest(dest, on_conflict)?;
   fs::create_dir_all(dest).map_err(|e| {
     ProplateError::create(ProplateErrorKind::Fs {
       concerned_paths: vec![dest.into()],
@@ -82,34 +318,155 @@ fn prepare_dest(dest: &str) -> ProplateResult<()> {
 
 /// Copies template file to the provided dest
 /// Files under "meta.exclude" won't be copied
-pub fn copy_files(template: &Template, dest: &str) -> ProplateResult<()> {
-  let src = &template.base_path;
+pub fn copy_files(
+  template: &Template,
+  dest: &str,
+  ctx: &Context,
+  on_conflict: ConflictPolicy,
+) -> ProplateResult<()> {
   let dest = Path::new(dest);
 
   println!("{}", logger::step("Copying..."));
 
-  pfs::copy_fdir(
-    src,
-    dest,
-    Some(
-      template
-        .conf
-        .exclude
-        .iter()
-        .map(|s| s.into())
-        .collect::<Vec<_>>(),
+  for (file, relative) in template_files(template, ctx)? {
+    let to = dest.join(&relative);
+
+    if to.exists() {
+      let overwrite = match on_conflict {
+        ConflictPolicy::Skip => false,
+        ConflictPolicy::Abort | ConflictPolicy::Overwrite => true,
+        ConflictPolicy::Prompt => confirm_overwrite(&relative, &to, &file)?,
+      };
+      if !overwrite {
+        println!(
+          "      {}",
+          logger::step(&format!("skipping {}", relative.display()))
+        );
+        continue;
+      }
+    }
+    (|| -> std::io::Result<()> {
+      if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+      }
+      fs::copy(&file, &to)?;
+      Ok(())
+    })()
+    .map_err(|e| {
+      ProplateError::create(ProplateErrorKind::Fs {
+        concerned_paths: vec![file.display().to_string(), to.display().to_string()],
+        operation: "copy".into(),
+      })
+      .with_ctx("gen:bootstrap:copy_files")
+      .with_cause(&e.to_string())
+    })?;
+  }
+
+  Ok(())
+}
+
+/// Shows the diff between the `existing` file and the `generated` one then asks whether to overwrite it
+fn confirm_overwrite(relative: &Path, existing: &Path, generated: &Path) -> ProplateResult<bool> {
+  let (old, new) = match (fs::read(existing), fs::read(generated)) {
+    (Ok(old), Ok(new)) => (old, new),
+    _ => return Ok(true),
+  };
+  if old == new {
+    return Ok(false);
+  }
+
+  println!(
+    "{}",
+    logger::title(&format!("{} already exists", relative.display()))
+  );
+  match (String::from_utf8(old), String::from_utf8(new)) {
+    (Ok(old), Ok(new)) => print!(
+      "{}",
+      DiffOptions::new()
+        .set_original_filename("existing")
+        .set_modified_filename("generated")
+        .create_patch(&old, &new)
     ),
-  )
-  .map_err(|e| {
+    _ => println!("(binary files differ)"),
+  }
+
+  Confirm::new(&format!("Overwrite {} ?", relative.display()))
+    .with_default(false)
+    .prompt()
+    .map_err(|e| {
+      ProplateError::create(ProplateErrorKind::Cli(CliErrorKind::Prompt))
+        .with_ctx("gen:bootstrap:confirm_overwrite")
+        .with_cause(&e.to_string())
+    })
+}
+
+/// Template files that end up in the generated project, i.e not under "meta.exclude", along with
+/// their path in the project in which the vars are bound e.g "src/$module_name.rs"
+fn template_files(template: &Template, ctx: &Context) -> ProplateResult<Vec<WalkdirPathBuf>> {
+  let files = template_paths(template)?;
+
+  let mut sources = HashMap::<PathBuf, PathBuf>::new();
+  let mut resolved = Vec::new();
+
+  for (file, relative) in files {
+    let to = bind_ctx_to_path(&relative, ctx)?;
+    if let Some(other) = sources.insert(to.clone(), relative.clone()) {
+      return Err(
+        ProplateError::create(ProplateErrorKind::Fs {
+          concerned_paths: vec![other.display().to_string(), relative.display().to_string()],
+          operation: "bind_ctx_to_path".into(),
+        })
+        .with_ctx("gen:bootstrap:template_files")
+        .with_cause(&format!("Both paths resolve to '{}'", to.display())),
+      );
+    }
+    resolved.push((file, to));
+  }
+
+  Ok(resolved)
+}
+
+/// Template files that are not under "meta.exclude", as is
+fn template_paths(template: &Template) -> ProplateResult<Vec<WalkdirPathBuf>> {
+  let src = &template.base_path;
+  let exclude = template.conf.exclude.iter().map(PathBuf::from).collect();
+  walk_dir_skip(src, exclude).map_err(|e| {
     ProplateError::create(ProplateErrorKind::Fs {
-      concerned_paths: vec![src.display().to_string(), dest.display().to_string()],
-      operation: "copy_fdir".into(),
+      concerned_paths: vec![src.display().to_string()],
+      operation: "walk_dir_skip".into(),
     })
-    .with_ctx("gen:bootstrap:copy_files")
+    .with_ctx("gen:bootstrap:template_files")
     .with_cause(&e.to_string())
   })
 }
 
+/// Replaces the vars of each component of a relative path, which must remain a single component
+fn bind_ctx_to_path(relative: &Path, ctx: &Context) -> ProplateResult<PathBuf> {
+  let mut bound = PathBuf::new();
+
+  for component in relative.iter() {
+    let component = component.to_string_lossy();
+    let value = interpolate(&component, ctx);
+    let invalid = value.is_empty() || value == "." || value == ".." || value.contains(['/', '\\']);
+    if value != component && invalid {
+      return Err(
+        ProplateError::create(ProplateErrorKind::Fs {
+          concerned_paths: vec![relative.display().to_string()],
+          operation: "bind_ctx_to_path".into(),
+        })
+        .with_ctx("gen:bootstrap:bind_ctx_to_path")
+        .with_cause(&format!(
+          "'{}' resolves to '{}' which is not a valid file name",
+          component, value
+        )),
+      );
+    }
+    bound.push(value);
+  }
+
+  Ok(bound)
+}
+
 pub fn cleanup(template: &Template) -> ProplateResult<()> {
   println!("{}", logger::step("cleaning up..."));
   fs::remove_dir_all(&template.base_path).map_err(|e| {
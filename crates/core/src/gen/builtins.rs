@@ -0,0 +1,22 @@
+/// Reserved vars `proplate create` supplies to every template
+pub const YEAR: &str = "_year";
+/// Formatted as "YYYY-MM-DD"
+pub const DATE: &str = "_date";
+pub const UUID: &str = "_uuid";
+pub const TEMPLATE_ID: &str = "_template_id";
+/// File name of the dest dir
+pub const DEST_NAME: &str = "_dest_name";
+/// Left undefined when git isn't configured
+pub const GIT_USER_NAME: &str = "_git_user_name";
+pub const GIT_USER_EMAIL: &str = "_git_user_email";
+
+/// Every builtin var, they are in scope without being declared
+pub const BUILTIN_VARS: [&str; 7] = [
+  YEAR,
+  DATE,
+  UUID,
+  TEMPLATE_ID,
+  DEST_NAME,
+  GIT_USER_NAME,
+  GIT_USER_EMAIL,
+];
@@ -0,0 +1,65 @@
+use std::collections::{BTreeMap, HashSet};
+
+use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult, TemplateErrorKind};
+
+use super::bootstrap::Context;
+use crate::template::{
+  interpolation::{referenced_vars, resolve},
+  Template,
+};
+
+/// Extends `ctx` with the "computed" vars of the template, each one being evaluated once the vars
+/// it refers to are. Values already in `ctx` (e.g supplied with "--arg") are kept as is
+pub fn compute(template: &Template, ctx: &Context) -> ProplateResult<Context> {
+  let mut ctx = ctx.clone();
+  let mut done = HashSet::new();
+
+  for key in template.conf.computed.keys() {
+    eval(template, key, &mut ctx, &mut done, &mut Vec::new())?;
+  }
+
+  Ok(ctx)
+}
+
+fn eval(
+  template: &Template,
+  key: &str,
+  ctx: &mut Context,
+  done: &mut HashSet<String>,
+  path: &mut Vec<String>,
+) -> ProplateResult<()> {
+  if done.contains(key) {
+    return Ok(());
+  }
+  if let Some(start) = path.iter().position(|k| k == key) {
+    let cycle = [&path[start..], &[key.to_string()]].concat().join(" -> ");
+    return Err(computed_error(template, key, &format!("Cycle: {}", cycle)));
+  }
+
+  let computed: &BTreeMap<_, _> = &template.conf.computed;
+  let expr = &computed[key];
+
+  path.push(key.into());
+  for var in referenced_vars(expr) {
+    if computed.contains_key(&var) {
+      eval(template, &var, ctx, done, path)?;
+    }
+  }
+  path.pop();
+
+  if !ctx.contains_key(key) {
+    let value = resolve(expr, ctx);
+    ctx.insert(key.into(), value);
+  }
+  done.insert(key.into());
+  Ok(())
+}
+
+fn computed_error(template: &Template, key: &str, cause: &str) -> ProplateError {
+  ProplateError::create(ProplateErrorKind::Template {
+    kind: TemplateErrorKind::Computed { key: key.into() },
+    location: template.fork_source.clone(),
+  })
+  .with_ctx("gen:computed:compute")
+  .with_cause(cause)
+}
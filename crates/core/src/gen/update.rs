@@ -0,0 +1,139 @@
+use std::{
+  collections::BTreeSet,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult};
+
+use crate::fs::walk::walk_dir;
+
+/// Extension of the file holding a template change that couldn't be applied
+pub const REJECT_EXT: &str = "rej";
+
+/// What happened to each project file during an update
+#[derive(Debug, Default)]
+pub struct UpdateReport {
+  pub added: Vec<PathBuf>,
+  pub updated: Vec<PathBuf>,
+  pub removed: Vec<PathBuf>,
+  /// Merged with conflict markers
+  pub conflicts: Vec<PathBuf>,
+  /// Couldn't be merged, the new version is written next to them as ".rej"
+  pub rejected: Vec<PathBuf>,
+  /// Removed from the template but modified in the project, left untouched
+  pub kept: Vec<PathBuf>,
+}
+
+/// Three-way merges the changes between two generations of the same template (`old` being the
+/// common ancestor and `new` the one to update to) into `project`
+pub fn merge_update(project: &Path, old: &Path, new: &Path) -> ProplateResult<UpdateReport> {
+  let mut report = UpdateReport::default();
+
+  for relative in relative_files(old)?.union(&relative_files(new)?) {
+    let base = read(&old.join(relative))?;
+    let theirs = read(&new.join(relative))?;
+    let target = project.join(relative);
+    let ours = read(&target)?;
+
+    // the template didn't change this file
+    if base == theirs {
+      continue;
+    }
+
+    match (base, theirs, ours) {
+      (None, Some(theirs), None) => {
+        write(&target, &theirs)?;
+        report.added.push(relative.clone());
+      }
+      // changed by the template but deleted in the project
+      (Some(_), Some(theirs), None) => {
+        write(&reject_path(&target), &theirs)?;
+        report.rejected.push(relative.clone());
+      }
+      (Some(base), None, Some(ours)) => match base == ours {
+        true => {
+          remove(&target)?;
+          report.removed.push(relative.clone());
+        }
+        false => report.kept.push(relative.clone()),
+      },
+      (base, Some(theirs), Some(ours)) => {
+        if ours == theirs {
+          continue;
+        }
+        if base.as_ref() == Some(&ours) {
+          write(&target, &theirs)?;
+          report.updated.push(relative.clone());
+          continue;
+        }
+
+        let base = base.unwrap_or_default();
+        match (
+          String::from_utf8(base),
+          String::from_utf8(ours),
+          String::from_utf8(theirs.clone()),
+        ) {
+          (Ok(base), Ok(ours), Ok(theirs)) => match diffy::merge(&base, &ours, &theirs) {
+            Ok(merged) => {
+              write(&target, merged.as_bytes())?;
+              report.updated.push(relative.clone());
+            }
+            Err(conflicted) => {
+              write(&target, conflicted.as_bytes())?;
+              report.conflicts.push(relative.clone());
+            }
+          },
+          // binary files cannot be merged
+          _ => {
+            write(&reject_path(&target), &theirs)?;
+            report.rejected.push(relative.clone());
+          }
+        }
+      }
+      _ => (),
+    }
+  }
+
+  Ok(report)
+}
+
+fn relative_files(dir: &Path) -> ProplateResult<BTreeSet<PathBuf>> {
+  let files = walk_dir(dir).map_err(|e| fs_error(dir, "walk_dir", &e))?;
+  Ok(files.into_iter().map(|(_, relative)| relative).collect())
+}
+
+fn reject_path(path: &Path) -> PathBuf {
+  let mut path = path.as_os_str().to_owned();
+  path.push(format!(".{}", REJECT_EXT));
+  path.into()
+}
+
+fn read(path: &Path) -> ProplateResult<Option<Vec<u8>>> {
+  match path.is_file() {
+    true => fs::read(path)
+      .map(Some)
+      .map_err(|e| fs_error(path, "read", &e)),
+    false => Ok(None),
+  }
+}
+
+fn write(path: &Path, content: &[u8]) -> ProplateResult<()> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| fs_error(parent, "create_dir_all", &e))?;
+  }
+  fs::write(path, content).map_err(|e| fs_error(path, "write", &e))
+}
+
+fn remove(path: &Path) -> ProplateResult<()> {
+  fs::remove_file(path).map_err(|e| fs_error(path, "remove_file", &e))
+}
+
+fn fs_error(path: &Path, operation: &str, e: &std::io::Error) -> ProplateError {
+  ProplateError::create(ProplateErrorKind::Fs {
+    concerned_paths: vec![path.display().to_string()],
+    operation: operation.into(),
+  })
+  .with_ctx("gen:update:merge")
+  .with_cause(&e.to_string())
+}
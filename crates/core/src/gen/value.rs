@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::bootstrap::Context;
+
+/// Value bound to a var of the ctx, (de)serialized as its json counterpart
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum Value {
+  Bool(bool),
+  Int(i64),
+  Float(f64),
+  String(String),
+  /// e.g the options picked in a "MultiSelect"
+  List(Vec<Value>),
+  Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+  /// Items of a list, a string being read as comma separated values
+  pub fn items(&self) -> Vec<Value> {
+    match self {
+      Value::String(s) => s
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(Value::from)
+        .collect(),
+      Value::List(items) => items.clone(),
+      value => vec![value.clone()],
+    }
+  }
+
+  /// Whether the value holds in a condition, i.e it is neither false, zero, nor empty.
+  /// The string "false" is falsy as well
+  pub fn is_truthy(&self) -> bool {
+    match self {
+      Value::Bool(b) => *b,
+      Value::Int(i) => *i != 0,
+      Value::Float(f) => *f != 0.0,
+      Value::String(s) => !s.is_empty() && s != "false",
+      Value::List(items) => !items.is_empty(),
+      Value::Map(entries) => !entries.is_empty(),
+    }
+  }
+
+  /// Whether the value is "", an empty list or an empty map
+  pub fn is_empty(&self) -> bool {
+    match self {
+      Value::String(s) => s.is_empty(),
+      Value::List(items) => items.is_empty(),
+      Value::Map(entries) => entries.is_empty(),
+      _ => false,
+    }
+  }
+
+  /// Strings the value holds, e.g the items of a list of strings
+  pub fn strings(&self) -> Vec<&str> {
+    match self {
+      Value::String(s) => vec![s.as_str()],
+      Value::List(items) => items.iter().flat_map(Value::strings).collect(),
+      Value::Map(entries) => entries.values().flat_map(Value::strings).collect(),
+      _ => Vec::new(),
+    }
+  }
+
+  /// Compares the value to a text e.g a condition rhs, which is parsed according to the type of
+  /// the value so that `true` equals "true" and `8080` equals "8080.0"
+  pub fn matches(&self, text: &str) -> bool {
+    match self {
+      Value::Bool(b) => text.parse::<bool>() == Ok(*b),
+      Value::Int(i) => text.parse::<f64>() == Ok(*i as f64),
+      Value::Float(f) => text.parse::<f64>() == Ok(*f),
+      value => value.to_string() == text,
+    }
+  }
+
+  /// Field of a map or item of a list
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    match self {
+      Value::Map(entries) => entries.get(key),
+      Value::List(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+      _ => None,
+    }
+  }
+}
+
+/// Value at a dotted path of the ctx e.g "db.port" or "tools.0"
+pub fn lookup<'a>(ctx: &'a Context, path: &str) -> Option<&'a Value> {
+  let mut keys = path.split('.');
+  let root = ctx.get(keys.next()?)?;
+  keys.try_fold(root, |value, key| value.get(key))
+}
+
+/// Text the value is bound to, list items are joined with ", " while maps are written as json.
+/// Floats are written losslessly e.g `1.0` stays "1.0"
+impl Display for Value {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Value::Bool(b) => b.fmt(f),
+      Value::Int(i) => i.fmt(f),
+      // "1.0" rather than "1", which is what the user wrote
+      Value::Float(n) => write!(f, "{:?}", n),
+      Value::String(s) => f.write_str(s),
+      Value::List(items) => f.write_str(
+        &items
+          .iter()
+          .map(|item| item.to_string())
+          .collect::<Vec<_>>()
+          .join(", "),
+      ),
+      Value::Map(_) => f.write_str(&serde_json::to_string(self).map_err(|_| std::fmt::Error)?),
+    }
+  }
+}
+
+impl From<String> for Value {
+  fn from(value: String) -> Self {
+    Value::String(value)
+  }
+}
+
+impl From<&str> for Value {
+  fn from(value: &str) -> Self {
+    Value::String(value.into())
+  }
+}
+
+impl From<bool> for Value {
+  fn from(value: bool) -> Self {
+    Value::Bool(value)
+  }
+}
+
+impl From<i64> for Value {
+  fn from(value: i64) -> Self {
+    Value::Int(value)
+  }
+}
+
+impl From<f64> for Value {
+  fn from(value: f64) -> Self {
+    Value::Float(value)
+  }
+}
+
+impl From<Vec<String>> for Value {
+  fn from(value: Vec<String>) -> Self {
+    Value::List(value.into_iter().map(Value::String).collect())
+  }
+}
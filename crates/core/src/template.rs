@@ -1,13 +1,16 @@
-use std::{fmt::Display, path::PathBuf, process::exit};
+use std::{
+  fmt::Display,
+  path::{Path, PathBuf},
+};
 
-use proplate_errors::{ProplateError, ProplateErrorKind, TemplateErrorKind};
-use proplate_tui::logger::AsError;
+use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult, TemplateErrorKind};
 
-use self::config::TemplateConf;
+use self::config::{find_manifests, TemplateConf};
 
 pub mod config;
 pub mod inquirer;
 pub mod interpolation;
+pub mod lint;
 pub mod op;
 pub mod resolver;
 
@@ -19,10 +22,17 @@ pub struct Template {
   pub base_file_list: Vec<String>,
   /// Github repo if he template is from github
   pub fork_source: String,
+  /// Commit the template was resolved at, if it is a git repo
+  pub commit: Option<String>,
   pub conf: TemplateConf,
 }
 
 pub const META_CONF: &str = "meta.json";
+/// Where "meta.schema.json", i.e the output of `proplate schema`, is published
+pub const SCHEMA_URL: &str =
+  "https://raw.githubusercontent.com/YumeT023/proplate/master/meta.schema.json";
+/// Manifests a template may be configured with, it has to have exactly one of them
+pub const META_CONFS: [&str; 4] = [META_CONF, "meta.yaml", "meta.yml", "meta.toml"];
 
 impl Display for Template {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -39,37 +49,42 @@ impl Template {
     base_path: PathBuf,
     base_file_list: Vec<String>,
     fork_source: String,
-  ) -> Template {
-    Template::validate_template_filebase(&base_file_list, fork_source.clone());
-    Template {
+  ) -> ProplateResult<Template> {
+    let manifest = Template::validate_template_filebase(&base_path, fork_source.clone())?;
+    let conf = TemplateConf::new(&base_path, manifest)?;
+    Ok(Template {
       id,
-      base_path: base_path.clone(),
+      base_path,
       base_file_list,
       fork_source,
-      conf: TemplateConf::new(&base_path),
-    }
+      commit: None,
+      conf,
+    })
   }
 
   /// Validates main files
-  /// Namely ensures that meta.json is present
-  fn validate_template_filebase(files: &Vec<String>, location: String) {
-    let mut violations = Vec::<String>::new();
-
-    if !files.contains(&META_CONF.to_string()) {
-      violations.push(String::from("No `meta_json` conf file"));
-    }
+  /// Namely ensures that exactly one manifest is present, which is returned
+  fn validate_template_filebase(
+    base_path: &Path,
+    location: String,
+  ) -> ProplateResult<&'static str> {
+    let (kind, cause) = match find_manifests(base_path).as_slice() {
+      [manifest] => return Ok(manifest),
+      [] => (TemplateErrorKind::NoConfig, None),
+      manifests => (
+        TemplateErrorKind::Invalid,
+        Some(format!(
+          "Only one manifest is allowed, found {}",
+          manifests.join(", ")
+        )),
+      ),
+    };
 
-    if !violations.is_empty() {
-      eprintln!(
-        "{}",
-        ProplateError::create(ProplateErrorKind::Template {
-          kind: TemplateErrorKind::NoConfig,
-          location
-        })
-        .with_ctx("template:validate")
-        .print_err()
-      );
-      exit(1);
+    let mut error = ProplateError::create(ProplateErrorKind::Template { kind, location })
+      .with_ctx("template:validate");
+    if let Some(cause) = cause {
+      error = error.with_cause(&cause);
     }
+    Err(error)
   }
 }
@@ -1,68 +1,256 @@
+use glob::Pattern;
+use regex::Regex;
+use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 use std::{
+  collections::BTreeMap,
   fs,
   path::{Path, PathBuf},
+  sync::OnceLock,
 };
 
-use proplate_errors::{ProplateError, ProplateErrorKind, TemplateErrorKind};
+use proplate_errors::{
+  ArgErrorKind, ProplateError, ProplateErrorKind, ProplateResult, TemplateErrorKind,
+};
 
-use crate::fs::walk::{walk_dir, walk_dir_skip};
+use crate::{
+  fs::walk::{walk_dir, walk_dir_skip},
+  gen::{bootstrap::Context, builtins::BUILTIN_VARS, value::Value},
+};
 
 use super::{
-  op::{AdditionalOperation, Operation},
-  META_CONF,
+  interpolation::line_col,
+  op::{AdditionalOperation, Condition, Operation},
+  META_CONFS, SCHEMA_URL,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
 pub enum ArgType {
   Text,
   Select,
+  /// Yes/no question, bound as a boolean
+  Confirm,
+  /// Any of the options, bound as a list
+  MultiSelect,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Question asked when a project is created, the answer being bound to "key"
+///
+/// The answer is checked against the rules ("required", "pattern"...), an empty answer that
+/// isn't required skipping them
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Arg {
   pub key: String,
   pub q_type: ArgType,
   pub label: String,
+  /// Comma separated options for a "MultiSelect"
   pub default_value: Option<String>,
-  /// Only used when "key" equals "Select."
+  /// Only used when "q_type" equals "Select" or "MultiSelect"
   pub options: Option<Vec<String>>,
+  #[serde(default)]
+  pub required: bool,
+  /// Regex the whole value must match
+  pub pattern: Option<String>,
+  pub min_length: Option<usize>,
+  pub max_length: Option<usize>,
+  /// Bounds of a numeric value
+  pub min: Option<f64>,
+  pub max: Option<f64>,
+  /// Shown instead of the reason the value is rejected
+  pub error_message: Option<String>,
+  /// Only asks the arg if these conditions hold against the previous answers
+  #[serde(default = "Vec::new")]
+  #[schemars(default)]
+  pub when: Vec<Condition>,
+  /// "pattern" anchored to the whole value, compiled on first use
+  #[serde(skip)]
+  compiled: OnceLock<Result<Regex, regex::Error>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Reason a "MultiSelect" without selected option is rejected when it is required
+pub const OPTION_REQUIRED: &str = "At least an option is required";
+
+impl Arg {
+  /// Ensures that `value` complies with the rules, returning the reason it doesn't otherwise
+  pub fn check(&self, value: &str) -> Result<(), String> {
+    self.violation(value).map_or(Ok(()), |reason| {
+      Err(self.error_message.clone().unwrap_or(reason))
+    })
+  }
+
+  /// Compiled "pattern", if any
+  pub fn regex(&self) -> Option<&Result<Regex, regex::Error>> {
+    let pattern = self.pattern.as_ref()?;
+    Some(
+      self
+        .compiled
+        .get_or_init(|| Regex::new(&format!("^(?:{})$", pattern))),
+    )
+  }
+
+  fn violation(&self, value: &str) -> Option<String> {
+    if value.is_empty() {
+      return self.required.then(|| "A value is required".into());
+    }
+
+    let length = value.chars().count();
+    if let (Some(pattern), Some(regex)) = (&self.pattern, self.regex()) {
+      match regex {
+        Ok(re) if !re.is_match(value) => return Some(format!("Must match '{}'", pattern)),
+        Err(e) => return Some(format!("Invalid pattern '{}': {}", pattern, e)),
+        _ => (),
+      }
+    }
+    if self.min_length.is_some_and(|min| length < min) {
+      return Some(format!(
+        "Must be at least {} chars long",
+        self.min_length.unwrap()
+      ));
+    }
+    if self.max_length.is_some_and(|max| length > max) {
+      return Some(format!(
+        "Must be at most {} chars long",
+        self.max_length.unwrap()
+      ));
+    }
+
+    if self.min.is_none() && self.max.is_none() {
+      return None;
+    }
+    let Ok(number) = value.parse::<f64>() else {
+      return Some("Must be a number".into());
+    };
+    if self.min.is_some_and(|min| number < min) {
+      return Some(format!("Must be at least {}", self.min.unwrap()));
+    }
+    if self.max.is_some_and(|max| number > max) {
+      return Some(format!("Must be at most {}", self.max.unwrap()));
+    }
+    None
+  }
+
+  /// Whether the arg is asked given the answers collected so far
+  pub fn is_relevant(&self, ctx: &Context) -> ProplateResult<bool> {
+    Condition::all_hold(&self.when, ctx)
+  }
+
+  /// Ensures that a value supplied without prompting (cli, answers file...) fits the arg and its
+  /// rules.
+  /// A string supplied to a "MultiSelect" is read as comma separated options
+  pub fn validate(&self, value: Value) -> ProplateResult<Value> {
+    let invalid = |cause: &str| {
+      ProplateError::create(ProplateErrorKind::Arg {
+        key: self.key.clone(),
+        kind: ArgErrorKind::InvalidValue {
+          value: value.to_string(),
+        },
+      })
+      .with_ctx("template:arg:validate")
+      .with_cause(cause)
+    };
+    let options = self.options.clone().unwrap_or_default();
+    let expect_options = || format!("Expected one of: {}", options.join(", "));
+
+    let is_option = |value: &Value| options.contains(&value.to_string());
+
+    match (&self.q_type, &value) {
+      (ArgType::MultiSelect, _) => {
+        let items = value.items();
+        if items.is_empty() && self.required {
+          let reason = self.error_message.as_deref();
+          return Err(invalid(reason.unwrap_or(OPTION_REQUIRED)));
+        }
+        match items.iter().all(is_option) {
+          true => Ok(Value::List(
+            items.iter().map(|item| item.to_string().into()).collect(),
+          )),
+          false => Err(invalid(&expect_options())),
+        }
+      }
+      (_, Value::List(_) | Value::Map(_)) => Err(invalid("Expected a single value")),
+      // numbers and booleans are text as well
+      (ArgType::Text, _) => match self.check(&value.to_string()) {
+        Ok(()) => Ok(value.to_string().into()),
+        Err(reason) => Err(invalid(&reason)),
+      },
+      (ArgType::Select, _) => match is_option(&value) {
+        true => Ok(value.to_string().into()),
+        false => Err(invalid(&expect_options())),
+      },
+      (ArgType::Confirm, _) => match value.to_string().parse::<bool>() {
+        Ok(b) => Ok(b.into()),
+        Err(_) => Err(invalid("Expected either true or false")),
+      },
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct TemplateConf {
+  /// Schema the manifest is checked against by editors, see `proplate schema`
+  #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+  pub schema: Option<String>,
   /// Template id
   pub id: String,
   /// Auxiliary proplate utils
   /// for example, a "License" file that is only copied if the "License" arg is set to "MIT"
   #[serde(default = "Vec::new")]
+  #[schemars(default)]
   pub exclude: Vec<String>,
   /// Arguments that Proplate will ask when a project is created using the associated template
   pub args: Vec<Arg>,
+  /// Vars derived from the answers, e.g "crate_name": "${project_name | snake_case}"
+  #[serde(default)]
+  pub computed: BTreeMap<String, String>,
   /// List of files containing dynamic variables
   /// used by Proplate to prevent having to go through every template file
   #[serde(default = "Vec::new")]
+  #[schemars(default)]
   pub dynamic_files: Vec<String>,
   #[serde(default = "Vec::new")]
+  #[schemars(default)]
   pub additional_operations: Vec<AdditionalOperation>,
 
   #[serde(default = "TemplateConf::default_keep_meta")]
   pub keep_meta: bool,
 
+  /// Fail when a var of the dynamic files cannot be bound instead of replacing it with ""
+  #[serde(default)]
+  pub strict: bool,
+
   /// Prevent examining dyn files repeatedly.
   #[serde(skip)]
   pub require_dyn_file_analysis: bool,
 }
 
 impl TemplateConf {
-  pub fn new(path: &Path) -> TemplateConf {
-    let conf = path.join(META_CONF);
-    let meta_json = fs::read_to_string(conf).unwrap();
-    let mut config = parse_config(&meta_json, path.display().to_string().as_str());
+  /// Reads the template manifest, i.e whichever of [META_CONFS] the template has
+  pub fn new(path: &Path, manifest: &str) -> ProplateResult<TemplateConf> {
+    let mut config = read_config(path, manifest).map_err(|reason| {
+      ProplateError::create(ProplateErrorKind::Template {
+        kind: TemplateErrorKind::Invalid,
+        location: path.display().to_string(),
+      })
+      .with_ctx("template:parse_config")
+      .with_cause(&reason)
+    })?;
 
-    normalize(&mut config, path);
+    normalize(&mut config, path, manifest);
+
+    Ok(config)
+  }
 
-    config
+  /// Vars the template may refer to: its args, computed and builtin vars
+  pub fn declared_vars(&self) -> Vec<&str> {
+    self
+      .args
+      .iter()
+      .map(|arg| arg.key.as_str())
+      .chain(self.computed.keys().map(String::as_str))
+      .chain(BUILTIN_VARS)
+      .collect()
   }
 
   fn default_keep_meta() -> bool {
@@ -70,21 +258,155 @@ impl TemplateConf {
   }
 }
 
-fn parse_config(meta_json: &str, location: &str) -> TemplateConf {
-  serde_json::from_str(meta_json)
-    .map_err(|e| {
-      ProplateError::create(ProplateErrorKind::Template {
-        kind: TemplateErrorKind::Invalid,
-        location: location.into(),
-      })
-      .with_ctx("template:parse_config")
-      .with_cause(&e.to_string())
+/// JSON Schema of the manifest, published as "meta.schema.json"
+pub fn json_schema() -> String {
+  serde_json::to_string_pretty(&schema_for!(TemplateConf)).unwrap()
+}
+
+/// Points the manifest of the template dir to the published schema, so that editors can check it.
+/// A JSON manifest gets a "$schema" key while YAML and TOML ones get the comment their language
+/// servers look for
+pub fn link_schema(path: &Path) -> ProplateResult<()> {
+  let [manifest] = find_manifests(path)[..] else {
+    return Ok(());
+  };
+  let file = path.join(manifest);
+  let fs_error = |operation: &str, reason: String| {
+    ProplateError::create(ProplateErrorKind::Fs {
+      concerned_paths: vec![file.display().to_string()],
+      operation: operation.into(),
     })
-    .unwrap()
+    .with_ctx("template:link_schema")
+    .with_cause(&reason)
+  };
+
+  let source = fs::read_to_string(&file).map_err(|e| fs_error("read", e.to_string()))?;
+  let linked = match Path::new(manifest).extension().and_then(|ext| ext.to_str()) {
+    Some("yaml" | "yml") if !source.contains("$schema=") => {
+      format!("# yaml-language-server: $schema={}\n{}", SCHEMA_URL, source)
+    }
+    Some("toml") if !source.starts_with("#:schema") => {
+      format!("#:schema {}\n{}", SCHEMA_URL, source)
+    }
+    Some("yaml" | "yml" | "toml") => return Ok(()),
+    _ => {
+      let invalid = |reason: String| {
+        ProplateError::create(ProplateErrorKind::Template {
+          kind: TemplateErrorKind::Invalid,
+          location: path.display().to_string(),
+        })
+        .with_ctx("template:link_schema")
+        .with_cause(&reason)
+      };
+      let serde_json::Value::Object(fields) =
+        serde_json::from_str(&source).map_err(|e| invalid(format!("{}: {}", manifest, e)))?
+      else {
+        return Err(invalid(format!("{}: expected an object", manifest)));
+      };
+      if fields.contains_key("$schema") {
+        return Ok(());
+      }
+      // "$schema" goes first, the other keys keep their order
+      let mut linked = serde_json::Map::from_iter([("$schema".into(), SCHEMA_URL.into())]);
+      linked.extend(fields);
+      serde_json::to_string_pretty(&linked).unwrap() + "\n"
+    }
+  };
+
+  fs::write(&file, linked).map_err(|e| fs_error("write", e.to_string()))
+}
+
+/// Manifests that exist in the template dir
+pub fn find_manifests(path: &Path) -> Vec<&'static str> {
+  META_CONFS
+    .into_iter()
+    .filter(|manifest| path.join(manifest).is_file())
+    .collect()
+}
+
+/// Parses the manifest as is, i.e with paths relative to the template dir, the reason it cannot
+/// be parsed is given as "manifest:line:column: reason"
+pub fn read_config(path: &Path, manifest: &str) -> Result<TemplateConf, String> {
+  let source = fs::read_to_string(path.join(manifest)).map_err(|e| e.to_string())?;
+  let config = deserialize(&source, manifest)
+    .map_err(|(line, column, reason)| format!("{}:{}:{}: {}", manifest, line, column, reason))?;
+  check_patterns(&config).map_err(|reason| format!("{}: {}", manifest, reason))?;
+  Ok(config)
+}
+
+/// Ensures the regex of the arg rules as well as the regex and globs of the "Replace" operations
+/// can be compiled
+fn check_patterns(config: &TemplateConf) -> Result<(), String> {
+  for arg in &config.args {
+    if let (Some(pattern), Some(Err(e))) = (&arg.pattern, arg.regex()) {
+      return Err(format!(
+        "Arg '{}' pattern '{}' is not a valid regex ({})",
+        arg.key,
+        pattern,
+        regex_error(e)
+      ));
+    }
+  }
+
+  let ops = config
+    .additional_operations
+    .iter()
+    .flat_map(|additional_op| additional_op.all_operations());
+  for op in ops {
+    if let Operation::Replace { files, pattern, .. } = op {
+      Regex::new(pattern).map_err(|e| {
+        format!(
+          "Replace pattern '{}' is not a valid regex ({})",
+          pattern,
+          regex_error(&e)
+        )
+      })?;
+      for file in files {
+        Pattern::new(file)
+          .map_err(|e| format!("Replace glob '{}' is not valid ({})", file, e.msg))?;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Last line of the regex error, which is the actual reason e.g "unclosed group"
+fn regex_error(e: &regex::Error) -> String {
+  let reason = e.to_string();
+  let reason = reason.lines().last().unwrap_or_default();
+  reason.trim_start_matches("error: ").into()
+}
+
+/// Deserializes the manifest according to its extension, errors being reported as
+/// (line, column, reason)
+fn deserialize(source: &str, manifest: &str) -> Result<TemplateConf, (usize, usize, String)> {
+  // json & yaml errors end with the position they occured at, which is reported separately
+  let strip_position = |reason: String, line: usize, column: usize| {
+    let suffix = format!(" at line {} column {}", line, column);
+    reason
+      .strip_suffix(&suffix)
+      .map(String::from)
+      .unwrap_or(reason)
+  };
+
+  match Path::new(manifest).extension().and_then(|ext| ext.to_str()) {
+    Some("yaml" | "yml") => serde_yaml::from_str(source).map_err(|e| {
+      let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+      (line, column, strip_position(e.to_string(), line, column))
+    }),
+    Some("toml") => toml::from_str(source).map_err(|e| {
+      let (line, column) = e.span().map_or((1, 1), |span| line_col(source, span.start));
+      (line, column, e.message().into())
+    }),
+    _ => serde_json::from_str(source).map_err(|e| {
+      let (line, column) = (e.line(), e.column());
+      (line, column, strip_position(e.to_string(), line, column))
+    }),
+  }
 }
 
-fn normalize(config: &mut TemplateConf, base: &Path) {
-  set_exclude_files(config, base);
+fn normalize(config: &mut TemplateConf, base: &Path, manifest: &str) {
+  set_exclude_files(config, base, manifest);
   set_additional_ops_files(config, base);
 
   config.require_dyn_file_analysis = true;
@@ -97,14 +419,14 @@ fn normalize(config: &mut TemplateConf, base: &Path) {
   }
 }
 
-fn set_exclude_files(config: &mut TemplateConf, base: &Path) {
+fn set_exclude_files(config: &mut TemplateConf, base: &Path, manifest: &str) {
   let files = &mut config.exclude;
 
   // Always exclude '.proplate_aux_utils' folder
   files.extend([".proplate_aux_utils".into(), ".git".into()]);
 
   if !config.keep_meta {
-    files.push(META_CONF.into());
+    files.push(manifest.into());
   }
 
   to_relative_all(files, base);
@@ -112,7 +434,7 @@ fn set_exclude_files(config: &mut TemplateConf, base: &Path) {
 
 fn set_additional_ops_files(config: &mut TemplateConf, base: &Path) {
   for additional_op in &mut config.additional_operations {
-    for op in &mut additional_op.operations {
+    for op in additional_op.all_operations_mut() {
       match op {
         Operation::Copy { file, dest } => {
           *file = to_relative(PathBuf::from(&file), base);
@@ -125,6 +447,20 @@ fn set_additional_ops_files(config: &mut TemplateConf, base: &Path) {
         Operation::Remove { files } => {
           to_relative_all(files, base);
         }
+        Operation::Move { from, to } => {
+          *from = to_relative(PathBuf::from(&from), base);
+          *to = to_relative(PathBuf::from(&to), base);
+        }
+        Operation::Write { dest, .. } | Operation::Append { dest, .. } => {
+          *dest = to_relative(PathBuf::from(&dest), base);
+        }
+        Operation::Replace { files, .. } => {
+          // the files are globs, which the base path must not be taken for
+          to_relative_all(
+            files,
+            Path::new(&Pattern::escape(&base.display().to_string())),
+          );
+        }
       }
     }
   }
@@ -145,7 +481,7 @@ fn populate_dynamic_files(config: &mut TemplateConf, base: &Path) {
     exclude,
     ..
   } = config;
-  let exclude_paths = exclude.iter().map(|s| PathBuf::from(s)).collect::<Vec<_>>();
+  let exclude_paths = exclude.iter().map(PathBuf::from).collect::<Vec<_>>();
   *dynamic_files = walk_dir_skip(base, exclude_paths)
     .expect("Walk dir")
     .iter()
@@ -161,30 +497,24 @@ fn update_dynamic_files(config: &mut TemplateConf, base: &Path) {
   } = config;
   to_relative_all(dynamic_files, base /* to */);
 
+  // recursively expand the dynamic files, each file being listed once so that it is bound once
   let mut expanded = Vec::new();
-
-  // recursively expand the dynamic files
   for path in dynamic_files.iter() {
     if let Ok(files) = walk_dir(Path::new(path)) {
-      let paths = files
-        .into_iter()
-        .filter_map(|(file, _)| {
-          let file = file.display().to_string();
-          if exclude.contains(&file) {
-            return None;
-          }
-          Some(file)
-        })
-        .collect::<Vec<_>>();
-      expanded.extend(paths);
+      for (file, _) in files {
+        let file = file.display().to_string();
+        if !exclude.contains(&file) && !expanded.contains(&file) {
+          expanded.push(file);
+        }
+      }
     }
   }
 
-  dynamic_files.extend(expanded);
+  *dynamic_files = expanded;
 }
 
-fn to_relative_all(files: &mut Vec<String>, to: &Path) {
-  for file in files.into_iter() {
+fn to_relative_all(files: &mut [String], to: &Path) {
+  for file in files.iter_mut() {
     *file = to_relative(PathBuf::from(&file), to);
   }
 }
@@ -1,9 +1,15 @@
 use std::process::exit;
 
-use inquire::{error::InquireResult, Select, Text};
+use inquire::{
+  error::InquireResult,
+  list_option::ListOption,
+  validator::{ErrorMessage, Validation},
+  Confirm, MultiSelect, Select, Text,
+};
 use proplate_tui::logger::AsError;
 
-use super::config::{Arg, ArgType};
+use super::config::{Arg, ArgType, OPTION_REQUIRED};
+use crate::gen::value::Value;
 
 use proplate_errors::{CliErrorKind, ProplateError, ProplateErrorKind};
 
@@ -17,10 +23,12 @@ pub struct InputAttr {
 pub enum Input<'a> {
   Text(Text<'a>, InputAttr),
   Select(Select<'a, String>, InputAttr),
+  Confirm(Confirm<'a>, InputAttr),
+  MultiSelect(MultiSelect<'a, String>, InputAttr),
 }
 
 impl<'a> Input<'a> {
-  fn handle_prompt(result: InquireResult<String>) -> String {
+  fn handle_prompt<T>(result: InquireResult<T>) -> T {
     match result {
       Ok(t) => t,
       Err(e) => {
@@ -37,7 +45,7 @@ impl<'a> Input<'a> {
     }
   }
 
-  pub fn prompt(&self) -> String {
+  pub fn prompt(&self) -> Value {
     match self {
       Input::Text(p, attr) => {
         let p = p.clone();
@@ -45,14 +53,47 @@ impl<'a> Input<'a> {
           p.with_initial_value(attr.default.clone().unwrap_or("".to_string()).as_ref())
             .prompt(),
         )
+        .into()
       }
-      Input::Select(p, _) => Self::handle_prompt(p.clone().prompt()),
+      Input::Select(p, _) => Self::handle_prompt(p.clone().prompt()).into(),
+      Input::Confirm(p, attr) => {
+        let p = match attr.default.as_deref() {
+          Some(default) => p.clone().with_default(default == "true"),
+          None => p.clone(),
+        };
+        Self::handle_prompt(p.prompt()).into()
+      }
+      Input::MultiSelect(p, attr) => {
+        let defaults = Value::from(attr.default.clone().unwrap_or_default()).items();
+        let selected = p
+          .options
+          .iter()
+          .enumerate()
+          .filter(|(_, option)| defaults.iter().any(|d| d.matches(option)))
+          .map(|(i, _)| i)
+          .collect::<Vec<_>>();
+        Self::handle_prompt(p.clone().with_default(&selected).prompt()).into()
+      }
+    }
+  }
+
+  /// Overrides the default value of the arg, e.g once interpolated
+  pub fn with_default(mut self, default: Option<String>) -> Self {
+    match &mut self {
+      Input::Select(_, attr)
+      | Input::Text(_, attr)
+      | Input::Confirm(_, attr)
+      | Input::MultiSelect(_, attr) => attr.default = default,
     }
+    self
   }
 
   pub fn get_attr(&self) -> &InputAttr {
     match self {
-      Input::Select(_, attr) | Input::Text(_, attr) => attr,
+      Input::Select(_, attr)
+      | Input::Text(_, attr)
+      | Input::Confirm(_, attr)
+      | Input::MultiSelect(_, attr) => attr,
     }
   }
 }
@@ -65,7 +106,14 @@ impl<'a> From<&'a Arg> for Input<'a> {
           default: value.default_value.clone(),
           name: value.key.clone(),
         };
-        Input::Text(Text::new(&value.label), attr)
+        let arg = value.clone();
+        let validator = move |input: &str| {
+          Ok(match arg.check(input) {
+            Ok(()) => Validation::Valid,
+            Err(reason) => Validation::Invalid(ErrorMessage::Custom(reason)),
+          })
+        };
+        Input::Text(Text::new(&value.label).with_validator(validator), attr)
       }
       ArgType::Select => {
         let options = value.options.clone().unwrap_or_default();
@@ -75,6 +123,33 @@ impl<'a> From<&'a Arg> for Input<'a> {
         };
         Input::Select(Select::new(&value.label, options), attr)
       }
+      ArgType::Confirm => {
+        let attr = InputAttr {
+          default: value.default_value.clone(),
+          name: value.key.clone(),
+        };
+        Input::Confirm(Confirm::new(&value.label), attr)
+      }
+      ArgType::MultiSelect => {
+        let options = value.options.clone().unwrap_or_default();
+        let attr = InputAttr {
+          default: value.default_value.clone(),
+          name: value.key.clone(),
+        };
+        let arg = value.clone();
+        let validator = move |selected: &[ListOption<&String>]| {
+          Ok(match selected.is_empty() && arg.required {
+            true => Validation::Invalid(ErrorMessage::Custom(
+              (arg.error_message.clone()).unwrap_or(OPTION_REQUIRED.into()),
+            )),
+            false => Validation::Valid,
+          })
+        };
+        Input::MultiSelect(
+          MultiSelect::new(&value.label, options).with_validator(validator),
+          attr,
+        )
+      }
     }
   }
 }
@@ -1,50 +1,321 @@
+use std::sync::OnceLock;
+
 use regex::Regex;
 
-use crate::gen::bootstrap::Context;
+use crate::gen::{
+  bootstrap::Context,
+  value::{lookup, Value},
+};
+
+use self::filter::{parse_quoted, Filter};
+
+pub mod block;
+pub mod filter;
+
+/// `$name` or `${...}`, both may be escaped with a backslash
+const INTERPOLATION_PATTERN: &str =
+  r#"(\\*)\$(?:\{((?:[^}"]|"(?:[^"\\]|\\.)*")*)\}|([a-zA-Z_][a-zA-Z0-9_]*)\b)"#;
+
+const NAME_PATTERN: &str = r"^[a-zA-Z_][a-zA-Z0-9_]*$";
+
+/// Name followed by fields of maps or indexes of lists, e.g "db.port" or "tools.0"
+const PATH_PATTERN: &str = r"^[a-zA-Z_][a-zA-Z0-9_]*(\.[a-zA-Z0-9_]+)*$";
+
+/// Environment variable e.g "env:USER", only bound by `interpolate_with_env`
+const ENV_PATTERN: &str = r"^env:[a-zA-Z_][a-zA-Z0-9_]*$";
+
+const ENV_PREFIX: &str = "env:";
+
+static INTERPOLATION_REGEX: OnceLock<Regex> = OnceLock::new();
+static NAME_REGEX: OnceLock<Regex> = OnceLock::new();
+static PATH_REGEX: OnceLock<Regex> = OnceLock::new();
+static ENV_REGEX: OnceLock<Regex> = OnceLock::new();
 
-const INTERPOLATION_PATTERN: &str = r"(\\*)\$([a-zA-Z_][a-zA-Z0-9_]*)\b";
+/// Compiles `pattern` on first use only
+fn compiled(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+  cell.get_or_init(|| Regex::new(pattern).unwrap())
+}
+
+fn create_regex() -> &'static Regex {
+  compiled(&INTERPOLATION_REGEX, INTERPOLATION_PATTERN)
+}
+
+pub(crate) fn name_regex() -> &'static Regex {
+  compiled(&NAME_REGEX, NAME_PATTERN)
+}
 
-fn create_regex() -> Regex {
-  Regex::new(INTERPOLATION_PATTERN).unwrap()
+pub(crate) fn path_regex() -> &'static Regex {
+  compiled(&PATH_REGEX, PATH_PATTERN)
+}
+
+/// Braced var: `${name}`, `${db.port}`, `${name:-fallback}`, `${name | snake_case | upper}`...
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+  pub name: String,
+  /// Used when the var is missing or empty
+  pub default: Option<String>,
+  pub filters: Vec<Filter>,
+}
+
+impl Expr {
+  /// Parses what is between the braces of `${...}`
+  pub fn parse(source: &str) -> Result<Expr, String> {
+    Expr::parse_with_env(source, false)
+  }
+
+  /// Same as `parse`, the name being possibly an environment variable e.g "env:USER"
+  fn parse_with_env(source: &str, with_env: bool) -> Result<Expr, String> {
+    let mut segments = split_pipes(source).into_iter();
+    let head = segments.next().unwrap_or_default();
+
+    let (name, default) = match head.split_once(":-") {
+      Some((name, default)) => (name.trim(), Some(parse_default(default.trim())?)),
+      None => (head.trim(), None),
+    };
+
+    let is_env = with_env && compiled(&ENV_REGEX, ENV_PATTERN).is_match(name);
+    if !is_env && !path_regex().is_match(name) {
+      return Err(format!("Invalid var name '{}'", name));
+    }
+
+    Ok(Expr {
+      name: name.into(),
+      default,
+      filters: segments.map(Filter::parse).collect::<Result<_, _>>()?,
+    })
+  }
+
+  /// Value of the expression in the given ctx, `None` if it is undefined and has no default
+  pub fn eval(&self, ctx: &Context) -> Option<String> {
+    let value = match (
+      lookup(ctx, &self.name).map(|v| v.to_string()),
+      &self.default,
+    ) {
+      (Some(value), Some(default)) if value.is_empty() => default.clone(),
+      (Some(value), _) => value,
+      (None, Some(default)) => default.clone(),
+      (None, None) => return None,
+    };
+    Some(self.filters.iter().fold(value, |value, f| f.apply(&value)))
+  }
+}
+
+/// Var occurrence that cannot be bound
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unresolved {
+  /// As written in the source, e.g "$name" or "${name | upper}"
+  pub var: String,
+  /// Byte offset of the var in the source
+  pub offset: usize,
+  pub reason: String,
+}
+
+impl Unresolved {
+  /// 1-based (line, column) of the var in `source`
+  pub fn line_col(&self, source: &str) -> (usize, usize) {
+    line_col(source, self.offset)
+  }
+}
+
+pub(crate) fn line_col(source: &str, offset: usize) -> (usize, usize) {
+  let before = &source[..offset];
+  let line = before.matches('\n').count() + 1;
+  let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+  (line, before[line_start..].chars().count() + 1)
+}
+
+/// Default value may be quoted to hold reserved chars such as '|'
+fn parse_default(source: &str) -> Result<String, String> {
+  if !source.starts_with('"') {
+    return Ok(source.into());
+  }
+  match parse_quoted(source)? {
+    (value, "") => Ok(value),
+    (_, rest) => Err(format!("Unexpected '{}' after default value", rest)),
+  }
+}
+
+/// Splits on the '|' that aren't quoted
+fn split_pipes(source: &str) -> Vec<&str> {
+  let mut segments = Vec::new();
+  let (mut start, mut quoted, mut escaped) = (0, false, false);
+
+  for (i, c) in source.char_indices() {
+    match c {
+      _ if escaped => escaped = false,
+      '\\' if quoted => escaped = true,
+      '"' => quoted = !quoted,
+      '|' if !quoted => {
+        segments.push(&source[start..i]);
+        start = i + 1;
+      }
+      _ => (),
+    }
+  }
+
+  segments.push(&source[start..]);
+  segments
 }
 
 /// Regex-based var binding replacement inside of a string slice
 ///
+/// Supports `$name`, `${name}`, defaults `${name:-fallback}` and filters `${name | kebab_case}`.
+/// A braced var that cannot be parsed, or is undefined and has no default, is left as is
+///
 /// # Example
 /// ```
 /// use proplate_core::{gen::bootstrap::Context, template::interpolation::interpolate};
 ///
 /// let mut ctx = Context::new();
-/// ctx.insert("name".to_string(), "proplate".to_string());
-/// println!("{}", interpolate("Hello $name", &ctx)); // "Hello proplate"
+/// ctx.insert("name".to_string(), "proplate".into());
+/// assert_eq!(interpolate("Hello $name", &ctx), "Hello proplate");
+/// assert_eq!(interpolate("${name}_cli", &ctx), "proplate_cli");
+/// assert_eq!(interpolate("${name | upper}", &ctx), "PROPLATE");
+/// assert_eq!(interpolate("${author:-anonymous}", &ctx), "anonymous");
+/// // left as is, unlike "$author"
+/// assert_eq!(interpolate("`${count} items`", &ctx), "`${count} items`");
+/// assert_eq!(interpolate("by $author", &ctx), "by ");
 /// ````
 pub fn interpolate(source: &str, ctx: &Context) -> String {
+  render(source, ctx, &mut Vec::new(), false)
+}
+
+/// Value of `source`, which is kept as is when `source` is nothing but a var e.g "$tools",
+/// otherwise `source` is interpolated
+pub fn resolve(source: &str, ctx: &Context) -> Value {
+  let lone_var = create_regex().captures(source).and_then(|caps| {
+    let whole = caps.get(0).unwrap();
+    if whole.as_str() != source || !caps.get(1).unwrap().as_str().is_empty() {
+      return None;
+    }
+    match (caps.get(2), caps.get(3)) {
+      (Some(expr), _) => Expr::parse(expr.as_str())
+        .ok()
+        .filter(|expr| expr.default.is_none() && expr.filters.is_empty())
+        .map(|expr| expr.name),
+      (_, name) => name.map(|name| name.as_str().to_string()),
+    }
+  });
+
+  match lone_var.and_then(|name| lookup(ctx, &name)) {
+    Some(value) => value.clone(),
+    None => interpolate(source, ctx).into(),
+  }
+}
+
+/// Same as `interpolate` but `${env:NAME}` is bound to the environment variable NAME as well,
+/// or to "" when it is not set. It is only meant for the default values of the args, everywhere
+/// else `${env:NAME}` is kept as is
+///
+/// # Example
+/// ```
+/// use proplate_core::{
+///   gen::bootstrap::Context,
+///   template::interpolation::{interpolate, interpolate_with_env},
+/// };
+///
+/// let ctx = Context::new();
+/// let source = "${env:PROPLATE_UNSET_VAR:-nobody}";
+/// assert_eq!(interpolate_with_env(source, &ctx), "nobody");
+/// assert_eq!(interpolate(source, &ctx), source);
+/// ```
+pub fn interpolate_with_env(source: &str, ctx: &Context) -> String {
+  let mut ctx = ctx.clone();
+  let names = create_regex()
+    .captures_iter(source)
+    .filter_map(|caps| Expr::parse_with_env(caps.get(2)?.as_str(), true).ok())
+    .map(|expr| expr.name);
+  for name in names {
+    if let Some(var) = name.strip_prefix(ENV_PREFIX) {
+      let value = std::env::var(var).unwrap_or_default();
+      ctx.insert(name.clone(), value.into());
+    }
+  }
+  render(source, &ctx, &mut Vec::new(), true)
+}
+
+/// Names of the vars `source` refers to, without the fields e.g "db" for `${db.port}`
+pub fn referenced_vars(source: &str) -> Vec<String> {
+  referenced_vars_at(source)
+    .into_iter()
+    .map(|(name, _)| name)
+    .collect()
+}
+
+/// Same as `referenced_vars` along with the byte offset of each reference
+pub(crate) fn referenced_vars_at(source: &str) -> Vec<(String, usize)> {
+  create_regex()
+    .captures_iter(source)
+    .filter(|caps| caps.get(1).unwrap().as_str().len() % 2 == 0)
+    .filter_map(|caps| {
+      let path = match (caps.get(2), caps.get(3)) {
+        (Some(expr), _) => Expr::parse(expr.as_str()).ok().map(|expr| expr.name),
+        (_, name) => name.map(|name| name.as_str().to_string()),
+      }?;
+      let offset = caps.get(0).unwrap().start() + caps.get(1).unwrap().len();
+      Some((
+        path.split('.').next().unwrap_or_default().to_string(),
+        offset,
+      ))
+    })
+    .collect()
+}
+
+/// Vars of `source` that are undefined in `ctx` or cannot be parsed
+pub fn find_unresolved(source: &str, ctx: &Context) -> Vec<Unresolved> {
+  let mut unresolved = Vec::new();
+  render(source, ctx, &mut unresolved, false);
+  unresolved
+}
+
+fn render(source: &str, ctx: &Context, unresolved: &mut Vec<Unresolved>, with_env: bool) -> String {
   let re = create_regex();
 
   let mut result = String::new();
   let mut last_end = 0;
 
   for caps in re.captures_iter(source) {
+    let whole = caps.get(0).unwrap();
     let escape = caps.get(1).unwrap().as_str();
-    let name = caps.get(2).unwrap().as_str();
+    // "$name" or "${...}"
+    let var = &whole.as_str()[escape.len()..];
 
     let escape_len = escape.len();
-    let value = ctx.get(name).map(|v| v.as_str()).unwrap_or("");
 
-    let unescaped_match = &source[last_end..caps.get(0).unwrap().start()];
+    let unescaped_match = &source[last_end..whole.start()];
     result.push_str(unescaped_match);
 
     if escape_len % 2 != 0 {
-      result.push_str(&format!(
-        "{}${}",
-        escape[..escape_len - 1].to_string(),
-        name
-      ));
+      result.push_str(&format!("{}{}", &escape[..escape_len - 1], var));
     } else {
+      // undefined "$name" is bound to an empty string while undefined or invalid braced vars are
+      // kept as is, as "${...}" is common in other languages e.g js template literals
+      let value = match (caps.get(2), caps.get(3)) {
+        (Some(expr), _) => match Expr::parse_with_env(expr.as_str(), with_env) {
+          Ok(expr) => expr
+            .eval(ctx)
+            .ok_or_else(|| (format!("'{}' is undefined", expr.name), var.to_string())),
+          Err(reason) => Err((reason, var.to_string())),
+        },
+        (_, Some(name)) => ctx
+          .get(name.as_str())
+          .map(|v| v.to_string())
+          .ok_or_else(|| (format!("'{}' is undefined", name.as_str()), String::new())),
+        _ => unreachable!(),
+      };
+
+      let value = value.unwrap_or_else(|(reason, fallback)| {
+        unresolved.push(Unresolved {
+          var: var.into(),
+          offset: whole.start() + escape_len,
+          reason,
+        });
+        fallback
+      });
       result.push_str(&format!("{}{}", escape, value));
     }
 
-    last_end = caps.get(0).unwrap().end();
+    last_end = whole.end();
   }
 
   result.push_str(&source[last_end..]);
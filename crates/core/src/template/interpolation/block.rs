@@ -0,0 +1,638 @@
+use std::slice::Iter;
+
+use super::{
+  filter::parse_quoted, line_col, name_regex, path_regex, referenced_vars_at, Unresolved,
+};
+use crate::{
+  gen::{
+    bootstrap::Context,
+    value::{self, Value},
+  },
+  template::op::StringCompareOp,
+};
+use regex::Regex;
+
+/// Tags that are parsed, any other `{% ... %}` is kept as is
+const KEYWORDS: [&str; 6] = ["if", "elif", "else", "endif", "for", "endfor"];
+
+/// Block tag which cannot be parsed, e.g. an `{% if %}` that is never closed, or whose test
+/// cannot be evaluated
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+  /// Byte offset of the tag in the source
+  pub offset: usize,
+  pub reason: String,
+}
+
+impl SyntaxError {
+  /// 1-based (line, column) of the tag in `source`
+  pub fn line_col(&self, source: &str) -> (usize, usize) {
+    line_col(source, self.offset)
+  }
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+  Text {
+    text: &'a str,
+    offset: usize,
+  },
+  /// What is between "{%" and "%}"
+  Tag {
+    content: &'a str,
+    offset: usize,
+    /// The tag as written, along with its line when it stands alone on it
+    raw: &'a str,
+  },
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+  Var(String),
+  Literal(String),
+}
+
+/// `var`, `not var`, `var == "value"`, `var != other_var`, `list contains "item"`...
+#[derive(Debug, Clone)]
+struct Test {
+  negate: bool,
+  lhs: Operand,
+  cmp: Option<(StringCompareOp, Operand)>,
+}
+
+#[derive(Debug)]
+enum Node<'a> {
+  Text {
+    text: &'a str,
+    offset: usize,
+  },
+  If {
+    /// `if` followed by the `elif`s, with the offset of their tag
+    branches: Vec<(Test, Tag<'a>, Vec<Node<'a>>)>,
+    /// `else` tag if any, along with its body
+    otherwise: Option<(Tag<'a>, Vec<Node<'a>>)>,
+    end: Tag<'a>,
+  },
+  For {
+    item: String,
+    list: String,
+    tag: Tag<'a>,
+    body: Vec<Node<'a>>,
+    end: Tag<'a>,
+  },
+}
+
+/// Offset and raw text of a tag, the latter being kept when the block is not ours
+#[derive(Debug, Clone, Copy)]
+struct Tag<'a> {
+  offset: usize,
+  raw: &'a str,
+}
+
+/// Renders the blocks and binds the vars of a dynamic file
+///
+/// Supports `{% if cond %}...{% elif cond %}...{% else %}...{% endif %}` and
+/// `{% for item in list %}...{% endfor %}`, string values being read as comma separated lists.
+/// A line holding nothing but a tag is removed along with its line break, `\{%` is kept as `{%`
+///
+/// The `declared` vars are falsy or empty when undefined, while the tags of a block which refers
+/// to another undefined var, e.g django's `{% if user.is_authenticated %}`, are kept as is
+///
+/// # Example
+/// ```
+/// use proplate_core::{gen::bootstrap::Context, template::interpolation::block::render};
+///
+/// let mut ctx = Context::new();
+/// ctx.insert("license".to_string(), "MIT".into());
+/// ctx.insert("features".to_string(), vec!["cli".to_string(), "tui".to_string()].into());
+///
+/// let source = r#"{% if license == "MIT" %}Free{% else %}Paid{% endif %}"#;
+/// assert_eq!(render(source, &ctx, &[]).unwrap(), "Free");
+///
+/// let source = "{% for f in features %}- $f\n{% endfor %}";
+/// assert_eq!(render(source, &ctx, &[]).unwrap(), "- cli\n- tui\n");
+///
+/// let source = "{% if docs %}Docs{% endif %}{% if user %}Logout{% endif %}";
+/// assert_eq!(render(source, &ctx, &["docs"]).unwrap(), "{% if user %}Logout{% endif %}");
+/// ```
+pub fn render(source: &str, ctx: &Context, declared: &[&str]) -> Result<String, SyntaxError> {
+  let nodes = parse(source)?;
+  let mut result = String::new();
+  render_nodes(&nodes, ctx, declared, &mut result, &mut Vec::new())?;
+  Ok(result)
+}
+
+/// Vars that would be rendered but are undefined in `ctx` or cannot be parsed, the vars of the
+/// blocks that are kept as is being left out
+pub fn find_unresolved(
+  source: &str,
+  ctx: &Context,
+  declared: &[&str],
+) -> Result<Vec<Unresolved>, SyntaxError> {
+  let nodes = parse(source)?;
+  let mut unresolved = Vec::new();
+  render_nodes(&nodes, ctx, declared, &mut String::new(), &mut unresolved)?;
+
+  // loops render the same vars several times
+  unresolved.sort_by_key(|u| u.offset);
+  unresolved.dedup();
+  Ok(unresolved)
+}
+
+/// Vars `source` refers to, whether rendered or not, along with their byte offset. The items
+/// of the loops are left out, as are the tests of the blocks over vars that aren't `declared`
+pub fn referenced_vars(
+  source: &str,
+  declared: &[&str],
+) -> Result<Vec<(String, usize)>, SyntaxError> {
+  let nodes = parse(source)?;
+  let mut vars = Vec::new();
+  collect_vars(&nodes, declared, &mut Vec::new(), &mut vars);
+  vars.sort_by_key(|(_, offset)| *offset);
+  Ok(vars)
+}
+
+fn collect_vars(
+  nodes: &[Node],
+  declared: &[&str],
+  items: &mut Vec<String>,
+  vars: &mut Vec<(String, usize)>,
+) {
+  let is_ours = |name: &str, items: &[String]| {
+    declared.contains(&root(name)) || items.iter().any(|item| item == root(name))
+  };
+
+  for node in nodes {
+    match node {
+      Node::Text { text, offset } => {
+        for (name, at) in referenced_vars_at(text) {
+          push_var(vars, items, &name, at + offset);
+        }
+      }
+      Node::If {
+        branches,
+        otherwise,
+        ..
+      } => {
+        let ours = branches
+          .iter()
+          .flat_map(|(test, _, _)| test.vars())
+          .all(|name| is_ours(name, items));
+        for (test, tag, body) in branches {
+          if ours {
+            for name in test.vars() {
+              push_var(vars, items, name, tag.offset);
+            }
+          }
+          collect_vars(body, declared, items, vars);
+        }
+        if let Some((_, body)) = otherwise {
+          collect_vars(body, declared, items, vars);
+        }
+      }
+      Node::For {
+        item,
+        list,
+        tag,
+        body,
+        ..
+      } => {
+        if !is_ours(list, items) {
+          collect_vars(body, declared, items, vars);
+          continue;
+        }
+        push_var(vars, items, list, tag.offset);
+        items.push(item.clone());
+        collect_vars(body, declared, items, vars);
+        items.pop();
+      }
+    }
+  }
+}
+
+/// Keeps the root of the var path unless it is the item of an enclosing loop
+fn push_var(vars: &mut Vec<(String, usize)>, items: &[String], name: &str, offset: usize) {
+  let root = root(name);
+  if !items.iter().any(|item| item == root) {
+    vars.push((root.to_string(), offset));
+  }
+}
+
+/// "db" for "db.port"
+fn root(name: &str) -> &str {
+  name.split('.').next().unwrap_or_default()
+}
+
+/// Whether one of `vars` is neither defined nor declared, the block then belonging to another
+/// template engine
+fn is_foreign<'v>(
+  mut vars: impl Iterator<Item = &'v str>,
+  ctx: &Context,
+  declared: &[&str],
+) -> bool {
+  vars.any(|name| !ctx.contains_key(root(name)) && !declared.contains(&root(name)))
+}
+
+fn render_nodes(
+  nodes: &[Node],
+  ctx: &Context,
+  declared: &[&str],
+  result: &mut String,
+  unresolved: &mut Vec<Unresolved>,
+) -> Result<(), SyntaxError> {
+  for node in nodes {
+    match node {
+      Node::Text { text, offset } => {
+        let mut found = Vec::new();
+        result.push_str(&super::render(text, ctx, &mut found, false));
+        unresolved.extend(found.into_iter().map(|u| Unresolved {
+          offset: u.offset + offset,
+          ..u
+        }));
+      }
+      Node::If {
+        branches,
+        otherwise,
+        end,
+      } => {
+        let vars = branches.iter().flat_map(|(test, _, _)| test.vars());
+        if is_foreign(vars, ctx, declared) {
+          for (_, tag, body) in branches {
+            result.push_str(tag.raw);
+            render_nodes(body, ctx, declared, result, unresolved)?;
+          }
+          if let Some((tag, body)) = otherwise {
+            result.push_str(tag.raw);
+            render_nodes(body, ctx, declared, result, unresolved)?;
+          }
+          result.push_str(end.raw);
+          continue;
+        }
+
+        let mut selected = otherwise.as_ref().map(|(_, body)| body);
+        for (test, tag, body) in branches {
+          if test.eval(ctx, tag.offset, unresolved)? {
+            selected = Some(body);
+            break;
+          }
+        }
+        if let Some(body) = selected {
+          render_nodes(body, ctx, declared, result, unresolved)?;
+        }
+      }
+      Node::For {
+        item,
+        list,
+        tag,
+        body,
+        end,
+      } => {
+        if is_foreign(std::iter::once(list.as_str()), ctx, declared) {
+          result.push_str(tag.raw);
+          render_nodes(body, ctx, declared, result, unresolved)?;
+          result.push_str(end.raw);
+          continue;
+        }
+
+        for value in lookup(ctx, list, tag.offset, unresolved).items() {
+          let mut ctx = ctx.clone();
+          ctx.insert(item.clone(), value);
+          render_nodes(body, &ctx, declared, result, unresolved)?;
+        }
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Value of `name`, undefined vars being empty
+fn lookup(ctx: &Context, name: &str, offset: usize, unresolved: &mut Vec<Unresolved>) -> Value {
+  value::lookup(ctx, name).cloned().unwrap_or_else(|| {
+    unresolved.push(Unresolved {
+      var: name.into(),
+      offset,
+      reason: format!("'{}' is undefined", name),
+    });
+    Value::String(String::new())
+  })
+}
+
+impl Operand {
+  fn eval(&self, ctx: &Context, offset: usize, unresolved: &mut Vec<Unresolved>) -> Value {
+    match self {
+      Operand::Var(name) => lookup(ctx, name, offset, unresolved),
+      Operand::Literal(value) => value.as_str().into(),
+    }
+  }
+}
+
+impl Test {
+  /// Whether the test of the tag at `offset` holds, failing when its operands cannot be compared
+  fn eval(
+    &self,
+    ctx: &Context,
+    offset: usize,
+    unresolved: &mut Vec<Unresolved>,
+  ) -> Result<bool, SyntaxError> {
+    let lhs = self.lhs.eval(ctx, offset, unresolved);
+    let holds = match &self.cmp {
+      Some((op, rhs)) => op
+        .eval(&lhs, &rhs.eval(ctx, offset, unresolved))
+        .map_err(|reason| SyntaxError { offset, reason })?,
+      None => lhs.is_truthy(),
+    };
+    Ok(holds != self.negate)
+  }
+
+  /// Vars the operands refer to
+  fn vars(&self) -> impl Iterator<Item = &str> {
+    let rhs = self.cmp.as_ref().map(|(_, rhs)| rhs);
+    std::iter::once(&self.lhs)
+      .chain(rhs)
+      .filter_map(|operand| match operand {
+        Operand::Var(name) => Some(name.as_str()),
+        Operand::Literal(_) => None,
+      })
+  }
+
+  fn parse(source: &str) -> Result<Test, String> {
+    let mut rest = source.trim();
+    let negate = match rest.strip_prefix("not ") {
+      Some(tail) => {
+        rest = tail.trim_start();
+        true
+      }
+      None => false,
+    };
+
+    let (lhs, tail) = parse_operand(rest)?;
+    let tail = tail.trim_start();
+    if tail.is_empty() {
+      return Ok(Test {
+        negate,
+        lhs,
+        cmp: None,
+      });
+    }
+
+    let (op, tail) = if let Some(tail) = tail.strip_prefix("==") {
+      (StringCompareOp::Eq, tail)
+    } else if let Some(tail) = tail.strip_prefix("!=") {
+      (StringCompareOp::NotEqual, tail)
+    } else if let Some(tail) = tail.strip_prefix("contains ") {
+      (StringCompareOp::Contains, tail)
+    } else {
+      return Err(format!(
+        "Expected '==', '!=' or 'contains', found '{}'",
+        tail
+      ));
+    };
+    let (rhs, tail) = parse_operand(tail.trim_start())?;
+    if !tail.trim().is_empty() {
+      return Err(format!("Unexpected '{}'", tail.trim()));
+    }
+
+    Ok(Test {
+      negate,
+      lhs,
+      cmp: Some((op, rhs)),
+    })
+  }
+}
+
+/// Quoted literal or var name, along with what follows it
+fn parse_operand(source: &str) -> Result<(Operand, &str), String> {
+  if source.starts_with('"') {
+    let (value, rest) = parse_quoted(source)?;
+    return Ok((Operand::Literal(value), rest));
+  }
+  let end = source
+    .find(|c: char| c.is_whitespace() || c == '=' || c == '!')
+    .unwrap_or(source.len());
+  let path = parse_var(&source[..end], path_regex())?;
+  Ok((Operand::Var(path), &source[end..]))
+}
+
+fn parse_var(source: &str, pattern: &Regex) -> Result<String, String> {
+  match pattern.is_match(source) {
+    true => Ok(source.into()),
+    false => Err(format!("Invalid var name '{}'", source)),
+  }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token<'_>>, SyntaxError> {
+  let mut tokens = Vec::new();
+  let (mut text_start, mut cursor) = (0, 0);
+
+  while let Some(found) = source[cursor..].find("{%") {
+    let start = cursor + found;
+
+    // "\{%" is kept as "{%"
+    if source[..start].ends_with('\\') {
+      push_text(&mut tokens, source, text_start, start - 1);
+      text_start = start;
+      cursor = start + 2;
+      continue;
+    }
+
+    let end = source[start..].find("%}").map(|i| start + i + 2);
+    let end = match end {
+      Some(end) if is_block_tag(&source[start + 2..end - 2]) => end,
+      // other tags, e.g. jinja's "{% raw %}", are text
+      Some(end) => {
+        cursor = end;
+        continue;
+      }
+      None if is_block_tag(&source[start + 2..]) => {
+        return Err(SyntaxError {
+          offset: start,
+          reason: "Unclosed tag, expected '%}'".into(),
+        })
+      }
+      None => break,
+    };
+
+    // a tag alone on its line takes the whole line with it
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[end..]
+      .find('\n')
+      .map_or(source.len(), |i| end + i + 1);
+    let standalone = line_start >= text_start
+      && source[line_start..start].trim().is_empty()
+      && source[end..line_end].trim().is_empty();
+    let (span_start, span_end) = match standalone {
+      true => (line_start, line_end),
+      false => (start, end),
+    };
+
+    push_text(&mut tokens, source, text_start, span_start);
+    tokens.push(Token::Tag {
+      content: source[start + 2..end - 2].trim(),
+      offset: start,
+      raw: &source[span_start..span_end],
+    });
+    text_start = span_end;
+    cursor = span_end;
+  }
+
+  push_text(&mut tokens, source, text_start, source.len());
+  Ok(tokens)
+}
+
+/// Whether the tag is one of ours, i.e it starts with one of [KEYWORDS]
+fn is_block_tag(content: &str) -> bool {
+  let keyword = content.split_whitespace().next().unwrap_or_default();
+  KEYWORDS.contains(&keyword)
+}
+
+fn push_text<'a>(tokens: &mut Vec<Token<'a>>, source: &'a str, start: usize, end: usize) {
+  if start < end {
+    tokens.push(Token::Text {
+      text: &source[start..end],
+      offset: start,
+    });
+  }
+}
+
+fn parse(source: &str) -> Result<Vec<Node<'_>>, SyntaxError> {
+  let tokens = tokenize(source)?;
+  let mut tokens = tokens.iter();
+  match parse_nodes(&mut tokens)? {
+    (nodes, None) => Ok(nodes),
+    (_, Some((content, tag))) => Err(SyntaxError {
+      offset: tag.offset,
+      reason: format!("Unexpected '{{% {} %}}'", content),
+    }),
+  }
+}
+
+/// Nodes along with the closing tag, if any
+type Parsed<'a> = (Vec<Node<'a>>, Option<(&'a str, Tag<'a>)>);
+
+/// Parses nodes until the end of the source or a closing tag (`elif`, `else`, `endif`, `endfor`)
+/// which is returned along with its content
+fn parse_nodes<'a>(tokens: &mut Iter<Token<'a>>) -> Result<Parsed<'a>, SyntaxError> {
+  let mut nodes = Vec::new();
+
+  while let Some(token) = tokens.next() {
+    let (content, tag) = match token {
+      Token::Text { text, offset } => {
+        nodes.push(Node::Text {
+          text,
+          offset: *offset,
+        });
+        continue;
+      }
+      Token::Tag {
+        content,
+        offset,
+        raw,
+      } => (
+        *content,
+        Tag {
+          offset: *offset,
+          raw,
+        },
+      ),
+    };
+    let offset = tag.offset;
+    let err = |reason: String| SyntaxError { offset, reason };
+
+    let (keyword, rest) = content
+      .split_once(char::is_whitespace)
+      .unwrap_or((content, ""));
+    match keyword {
+      "if" => nodes.push(parse_if(tokens, rest, tag)?),
+      "for" => {
+        let (item, list) = match rest.split_whitespace().collect::<Vec<_>>()[..] {
+          [item, "in", list] => (
+            parse_var(item, name_regex()).map_err(err)?,
+            parse_var(list, path_regex()).map_err(err)?,
+          ),
+          _ => return Err(err("Expected '{% for item in list %}'".into())),
+        };
+        let (body, end) = match parse_nodes(tokens)? {
+          (body, Some(("endfor", end))) => (body, end),
+          (_, Some((content, end))) => {
+            return Err(SyntaxError {
+              offset: end.offset,
+              reason: format!("Unexpected '{{% {} %}}' in a for block", content),
+            })
+          }
+          (_, None) => return Err(err("Unclosed for block, expected '{% endfor %}'".into())),
+        };
+        nodes.push(Node::For {
+          item,
+          list,
+          tag,
+          body,
+          end,
+        });
+      }
+      "elif" | "else" | "endif" | "endfor" => return Ok((nodes, Some((content, tag)))),
+      _ => unreachable!("only block tags are tokenized"),
+    }
+  }
+
+  Ok((nodes, None))
+}
+
+fn parse_if<'a>(
+  tokens: &mut Iter<Token<'a>>,
+  test: &str,
+  tag: Tag<'a>,
+) -> Result<Node<'a>, SyntaxError> {
+  let mut branches = Vec::new();
+  let (mut test, mut test_tag) = (test.to_string(), tag);
+
+  loop {
+    let parsed = Test::parse(&test).map_err(|reason| SyntaxError {
+      offset: test_tag.offset,
+      reason,
+    })?;
+    let (body, closing) = parse_nodes(tokens)?;
+    branches.push((parsed, test_tag, body));
+
+    match closing {
+      Some(("endif", end)) => {
+        return Ok(Node::If {
+          branches,
+          otherwise: None,
+          end,
+        })
+      }
+      Some(("else", else_tag)) => {
+        return match parse_nodes(tokens)? {
+          (otherwise, Some(("endif", end))) => Ok(Node::If {
+            branches,
+            otherwise: Some((else_tag, otherwise)),
+            end,
+          }),
+          (_, Some((content, closing))) => Err(SyntaxError {
+            offset: closing.offset,
+            reason: format!("Unexpected '{{% {} %}}' after '{{% else %}}'", content),
+          }),
+          (_, None) => Err(unclosed_if(tag.offset)),
+        }
+      }
+      Some((content, closing)) if content.starts_with("elif ") => {
+        test = content["elif ".len()..].to_string();
+        test_tag = closing;
+      }
+      Some((content, closing)) => {
+        return Err(SyntaxError {
+          offset: closing.offset,
+          reason: format!("Unexpected '{{% {} %}}' in an if block", content),
+        })
+      }
+      None => return Err(unclosed_if(tag.offset)),
+    }
+  }
+}
+
+fn unclosed_if(offset: usize) -> SyntaxError {
+  SyntaxError {
+    offset,
+    reason: "Unclosed if block, expected '{% endif %}'".into(),
+  }
+}
@@ -0,0 +1,100 @@
+use heck::{ToKebabCase, ToLowerCamelCase, ToPascalCase, ToSnakeCase};
+
+/// Transformation applied to a value, e.g. `${name | kebab_case}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+  KebabCase,
+  SnakeCase,
+  PascalCase,
+  CamelCase,
+  Upper,
+  Lower,
+  Trim,
+  Replace(String, String),
+}
+
+impl Filter {
+  /// Parses a single filter such as `upper` or `replace("a", "b")`
+  pub fn parse(source: &str) -> Result<Filter, String> {
+    let source = source.trim();
+    let (name, args) = match source.split_once('(') {
+      Some((name, rest)) => {
+        let args = rest
+          .strip_suffix(')')
+          .ok_or_else(|| format!("Missing ')' in filter '{}'", source))?;
+        (name.trim(), parse_args(args)?)
+      }
+      None => (source, Vec::new()),
+    };
+
+    let filter = match (name, args.as_slice()) {
+      ("kebab_case", []) => Filter::KebabCase,
+      ("snake_case", []) => Filter::SnakeCase,
+      ("pascal_case", []) => Filter::PascalCase,
+      ("camel_case", []) => Filter::CamelCase,
+      ("upper", []) => Filter::Upper,
+      ("lower", []) => Filter::Lower,
+      ("trim", []) => Filter::Trim,
+      ("replace", [from, to]) => Filter::Replace(from.clone(), to.clone()),
+      ("replace", _) => return Err("'replace' expects 2 args: replace(\"from\", \"to\")".into()),
+      _ => return Err(format!("Unknown filter '{}'", source)),
+    };
+
+    Ok(filter)
+  }
+
+  pub fn apply(&self, value: &str) -> String {
+    match self {
+      Filter::KebabCase => value.to_kebab_case(),
+      Filter::SnakeCase => value.to_snake_case(),
+      Filter::PascalCase => value.to_pascal_case(),
+      Filter::CamelCase => value.to_lower_camel_case(),
+      Filter::Upper => value.to_uppercase(),
+      Filter::Lower => value.to_lowercase(),
+      Filter::Trim => value.trim().to_string(),
+      Filter::Replace(from, to) => value.replace(from, to),
+    }
+  }
+}
+
+/// Comma separated list of quoted strings
+fn parse_args(source: &str) -> Result<Vec<String>, String> {
+  let mut args = Vec::new();
+  let mut rest = source.trim();
+
+  while !rest.is_empty() {
+    let (arg, tail) = parse_quoted(rest)?;
+    args.push(arg);
+    rest = tail.trim_start();
+    if let Some(tail) = rest.strip_prefix(',') {
+      rest = tail.trim_start();
+    } else if !rest.is_empty() {
+      return Err(format!("Expected ',' before '{}'", rest));
+    }
+  }
+
+  Ok(args)
+}
+
+/// Parses the double quoted string `source` starts with, returning it unescaped along with what
+/// follows it
+pub fn parse_quoted(source: &str) -> Result<(String, &str), String> {
+  let body = source
+    .strip_prefix('"')
+    .ok_or_else(|| format!("Expected a quoted string, found '{}'", source))?;
+
+  let mut value = String::new();
+  let mut chars = body.char_indices();
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '"' => return Ok((value, &body[i + 1..])),
+      '\\' => match chars.next() {
+        Some((_, escaped)) => value.push(escaped),
+        None => break,
+      },
+      c => value.push(c),
+    }
+  }
+
+  Err(format!("Unterminated string '{}'", source))
+}
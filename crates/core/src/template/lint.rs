@@ -0,0 +1,367 @@
+use std::{
+  cmp::Reverse,
+  collections::BTreeSet,
+  fmt::Display,
+  fs,
+  path::{Path, PathBuf},
+};
+
+use regex::Regex;
+
+use super::{
+  config::{find_manifests, read_config, ArgType, TemplateConf},
+  interpolation::{block, line_col, referenced_vars},
+  op::{Condition, Operation},
+  META_CONFS,
+};
+use crate::fs::walk::walk_dir_skip;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+/// Problem found in a template by `lint`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub message: String,
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let label = match self.severity {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    };
+    write!(f, "{}: {}", label, self.message)
+  }
+}
+
+/// Checks the template at `path` without generating it, namely that
+/// - it has exactly one manifest, which can be parsed
+/// - the paths the manifest refers to exist, either in the template or as the dest of a copy
+/// - "Select" and "MultiSelect" args have options
+/// - the vars refer to declared args, computed or builtin vars and every arg is used
+pub fn lint(path: &Path) -> Vec<Diagnostic> {
+  let mut diagnostics = Vec::new();
+
+  let manifest = match find_manifests(path).as_slice() {
+    [manifest] => *manifest,
+    [] => {
+      diagnostics.push(error(format!(
+        "No manifest found, expected one of {}",
+        META_CONFS.join(", ")
+      )));
+      return diagnostics;
+    }
+    manifests => {
+      diagnostics.push(error(format!(
+        "Only one manifest is allowed, found {}",
+        manifests.join(", ")
+      )));
+      return diagnostics;
+    }
+  };
+
+  let conf = match read_config(path, manifest) {
+    Ok(conf) => conf,
+    Err(reason) => {
+      diagnostics.push(error(reason));
+      return diagnostics;
+    }
+  };
+
+  check_options(&conf, &mut diagnostics);
+  check_paths(path, &conf, &mut diagnostics);
+  check_vars(path, manifest, &conf, &mut diagnostics);
+
+  // errors first
+  diagnostics.sort_by_key(|d| Reverse(d.severity));
+  diagnostics
+}
+
+fn error(message: String) -> Diagnostic {
+  Diagnostic {
+    severity: Severity::Error,
+    message,
+  }
+}
+
+fn warning(message: String) -> Diagnostic {
+  Diagnostic {
+    severity: Severity::Warning,
+    message,
+  }
+}
+
+fn check_options(conf: &TemplateConf, diagnostics: &mut Vec<Diagnostic>) {
+  for arg in &conf.args {
+    let needs_options = matches!(arg.q_type, ArgType::Select | ArgType::MultiSelect);
+    if needs_options && arg.options.as_ref().is_none_or(|o| o.is_empty()) {
+      diagnostics.push(error(format!(
+        "Arg '{}' is a {:?} without options",
+        arg.key, arg.q_type
+      )));
+    }
+  }
+}
+
+/// Paths created by the "Copy", "CopyDir" and "Move" operations, in order
+fn copy_dests(conf: &TemplateConf) -> Vec<(PathBuf /*src*/, PathBuf /*dest*/)> {
+  conf
+    .additional_operations
+    .iter()
+    .flat_map(|additional_op| additional_op.all_operations())
+    .filter_map(|op| match op {
+      Operation::Copy { file, dest } => Some((file.into(), dest.into())),
+      Operation::CopyDir { path, dest } => Some((path.into(), dest.into())),
+      Operation::Move { from, to } => Some((from.into(), to.into())),
+      _ => None,
+    })
+    .collect()
+}
+
+/// Template path `file` is copied or moved from, if it is created by an operation
+fn copy_source(conf: &TemplateConf, file: &Path) -> Option<PathBuf> {
+  copy_dests(conf).into_iter().rev().find_map(|(src, dest)| {
+    let rest = file.strip_prefix(&dest).ok()?;
+    Some(match rest.as_os_str().is_empty() {
+      true => src,
+      false => src.join(rest),
+    })
+  })
+}
+
+fn check_paths(base: &Path, conf: &TemplateConf, diagnostics: &mut Vec<Diagnostic>) {
+  let mut created = Vec::<PathBuf>::new();
+  let exists = |file: &str, created: &[PathBuf]| {
+    base.join(file).exists() || created.iter().any(|dest| Path::new(file).starts_with(dest))
+  };
+
+  for op in conf
+    .additional_operations
+    .iter()
+    .flat_map(|additional_op| additional_op.all_operations())
+  {
+    match op {
+      Operation::Copy { file, dest } => {
+        if !base.join(file).is_file() {
+          diagnostics.push(error(format!("Copy source '{}' does not exist", file)));
+        }
+        created.push(dest.into());
+      }
+      Operation::CopyDir { path, dest } => {
+        if !base.join(path).is_dir() {
+          diagnostics.push(error(format!("CopyDir source '{}' does not exist", path)));
+        }
+        created.push(dest.into());
+      }
+      Operation::Move { from, to } => {
+        // interpolated paths are only known once the args are answered
+        if referenced_vars(from).is_empty() && !exists(from, &created) {
+          diagnostics.push(error(format!("Move source '{}' does not exist", from)));
+        }
+        created.push(to.into());
+      }
+      Operation::Write { dest, .. } | Operation::Append { dest, .. } => {
+        created.push(dest.into());
+      }
+      Operation::Replace { .. } => (),
+      Operation::Remove { files } => {
+        for file in files.iter().filter(|file| !exists(file, &created)) {
+          diagnostics.push(error(format!("Remove target '{}' does not exist", file)));
+        }
+      }
+    }
+  }
+
+  for file in &conf.dynamic_files {
+    if !exists(file, &created) {
+      diagnostics.push(error(format!("Dynamic file '{}' does not exist", file)));
+    }
+  }
+}
+
+/// Files whose content is bound to the ctx, relative to `base`
+fn dynamic_files(base: &Path, skip: Vec<PathBuf>, conf: &TemplateConf) -> Vec<PathBuf> {
+  let walk = |path: &Path, skip: Vec<PathBuf>| {
+    walk_dir_skip(&base.join(path), skip)
+      .map(|files| {
+        files
+          .into_iter()
+          // a file is walked as itself, with an empty relative path
+          .map(|(_, relative)| match relative.as_os_str().is_empty() {
+            true => path.to_path_buf(),
+            false => path.join(relative),
+          })
+          .collect::<Vec<_>>()
+      })
+      .unwrap_or_default()
+  };
+
+  if conf.dynamic_files.is_empty() {
+    // every file ends up being dynamic, including the copied ones
+    let copied = copy_dests(conf)
+      .into_iter()
+      .flat_map(|(src, _)| walk(&src, Vec::new()));
+    return walk(Path::new(""), skip)
+      .into_iter()
+      .chain(copied)
+      .collect();
+  }
+
+  conf
+    .dynamic_files
+    .iter()
+    .map(PathBuf::from)
+    .flat_map(|file| match base.join(&file).exists() {
+      true => walk(&file, Vec::new()),
+      false => copy_source(conf, &file)
+        .map(|src| walk(&src, Vec::new()))
+        .unwrap_or_default(),
+    })
+    .collect()
+}
+
+fn check_vars(base: &Path, manifest: &str, conf: &TemplateConf, diagnostics: &mut Vec<Diagnostic>) {
+  let declared = conf.declared_vars().into_iter().collect::<BTreeSet<_>>();
+  let mut used = BTreeSet::<String>::new();
+
+  let mut check = |vars: Vec<String>, origin: &str, used: &mut BTreeSet<String>| {
+    for var in vars {
+      if !declared.contains(var.as_str()) {
+        diagnostics.push(error(format!("{} refers to undeclared '{}'", origin, var)));
+      }
+      used.insert(var);
+    }
+  };
+  let condition_vars = |conditions: &[Condition]| {
+    conditions
+      .iter()
+      .flat_map(Condition::operands)
+      .flat_map(referenced_vars)
+      .collect::<Vec<_>>()
+  };
+
+  for arg in &conf.args {
+    let origin = format!("Arg '{}'", arg.key);
+    check(condition_vars(&arg.when), &origin, &mut used);
+    if let Some(default) = &arg.default_value {
+      check(referenced_vars(default), &origin, &mut used);
+    }
+  }
+  for (key, expr) in &conf.computed {
+    let origin = format!("Computed '{}'", key);
+    check(referenced_vars(expr), &origin, &mut used);
+  }
+  for (i, additional_op) in conf.additional_operations.iter().enumerate() {
+    let origin = format!("Condition of additional_operations[{}]", i);
+    check(
+      condition_vars(&additional_op.conditions),
+      &origin,
+      &mut used,
+    );
+    for op in additional_op.all_operations() {
+      let replacement_args;
+      let mut groups = Vec::<String>::new();
+      let (name, texts) = match op {
+        Operation::Move { from, to } => ("Move", vec![from, to]),
+        Operation::Write { dest, content } => ("Write", vec![dest, content]),
+        Operation::Append { dest, content } => ("Append", vec![dest, content]),
+        Operation::Replace {
+          files,
+          pattern,
+          replacement,
+        } => {
+          // "$$" is a literal "$" and the groups of the pattern aren't args
+          groups = Regex::new(pattern)
+            .map(|re| re.capture_names().flatten().map(String::from).collect())
+            .unwrap_or_default();
+          replacement_args = replacement.replace("$$", "");
+          ("Replace", files.iter().chain([&replacement_args]).collect())
+        }
+        _ => continue,
+      };
+      let origin = format!("{} of additional_operations[{}]", name, i);
+      let vars = texts.into_iter().flat_map(|text| referenced_vars(text));
+      let vars = vars.filter(|var| !groups.contains(var));
+      check(vars.collect(), &origin, &mut used);
+    }
+  }
+
+  let skip = [".git", ".proplate_aux_utils", manifest]
+    .into_iter()
+    .chain(conf.exclude.iter().map(String::as_str))
+    .map(|file| base.join(file))
+    .collect::<Vec<_>>();
+
+  let mut paths = walk_dir_skip(base, skip.clone()).unwrap_or_default();
+  paths.sort();
+  for (_, relative) in paths {
+    let origin = format!("Path '{}'", relative.display());
+    check(
+      referenced_vars(&relative.to_string_lossy()),
+      &origin,
+      &mut used,
+    );
+  }
+
+  // undefined vars are bound to "" unless the template is strict
+  let severity = match conf.strict {
+    true => Severity::Error,
+    false => Severity::Warning,
+  };
+  let mut files = dynamic_files(base, skip, conf);
+  files.sort();
+  files.dedup();
+  for file in files {
+    let Ok(source) = fs::read_to_string(base.join(&file)) else {
+      continue;
+    };
+    let vars = match block::referenced_vars(&source, &conf.declared_vars()) {
+      Ok(vars) => vars,
+      Err(e) => {
+        let (line, col) = e.line_col(&source);
+        diagnostics.push(error(format!(
+          "{}:{}:{}: {}",
+          file.display(),
+          line,
+          col,
+          e.reason
+        )));
+        continue;
+      }
+    };
+
+    let mut reported = BTreeSet::new();
+    for (var, offset) in vars {
+      if !declared.contains(var.as_str()) && reported.insert(var.clone()) {
+        let (line, col) = line_col(&source, offset);
+        diagnostics.push(Diagnostic {
+          severity,
+          message: format!(
+            "{}:{}:{}: '{}' refers to no declared arg",
+            file.display(),
+            line,
+            col,
+            var
+          ),
+        });
+      }
+      used.insert(var);
+    }
+  }
+
+  for key in conf
+    .args
+    .iter()
+    .map(|arg| &arg.key)
+    .chain(conf.computed.keys())
+  {
+    if !used.contains(key) {
+      diagnostics.push(warning(format!("'{}' is never used", key)));
+    }
+  }
+}
@@ -1,62 +1,557 @@
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+  cmp::Ordering,
+  fs,
+  path::{Path, PathBuf},
+};
 
 use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult};
-use serde::{Deserialize, Serialize};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize};
 
-use super::interpolation::Interpolate;
-use crate::fs as pfs;
+use super::interpolation::{interpolate, resolve};
+use crate::{
+  fs as pfs,
+  gen::{bootstrap::Context, value::Value},
+};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
 pub enum StringCompareOp {
   Eq,
   NotEqual,
+  /// The lhs list holds the rhs, a string lhs being read as comma separated values
+  Contains,
+  /// The rhs list holds the lhs, e.g `"rhs": ["MIT", "Apache-2.0"]` or `"rhs": "$licenses"`
+  In,
+  NotIn,
+  /// The rhs regex matches the lhs, anywhere unless anchored with "^" and "$"
+  Matches,
+  StartsWith,
+  EndsWith,
+  /// The lhs is "", an empty list or map, the rhs is ignored
+  IsEmpty,
+  IsNotEmpty,
+  /// Versions such as "1.2.3", "v1.10" and "2.0-beta.1", or else numbers, e.g "0.5" > "0.25"
+  Gt,
+  Lt,
+  Gte,
+  Lte,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Condition {
-  pub lhs: String,
-  pub op: StringCompareOp,
-  pub rhs: String,
+/// Either a comparison or a group of conditions, e.g
+/// `{ "any": [{ "lhs": "$license", "op": "Eq", "rhs": "MIT" }, { "not": { ... } }] }`
+#[derive(Serialize, Debug, Clone, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum Condition {
+  Compare {
+    /// Either a text to interpolate or a lone var e.g "$tools" which keeps the type of its value
+    lhs: String,
+    op: StringCompareOp,
+    /// The strings it holds are interpolated like the lhs, left out by "IsEmpty" and "IsNotEmpty"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rhs: Option<Value>,
+  },
+  /// Holds if every condition holds, which is the case of an empty list
+  All {
+    all: Vec<Condition>,
+  },
+  /// Holds if at least one condition holds
+  Any {
+    any: Vec<Condition>,
+  },
+  Not {
+    not: Box<Condition>,
+  },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Every key a condition may have, which [Condition] is picked according to, so that the error
+/// tells which shape is wrong rather than that none of them matched
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCondition {
+  lhs: Option<String>,
+  op: Option<StringCompareOp>,
+  #[serde(default)]
+  rhs: Option<Value>,
+  all: Option<Vec<Condition>>,
+  any: Option<Vec<Condition>>,
+  not: Option<Box<Condition>>,
+}
+
+impl<'de> Deserialize<'de> for Condition {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let RawCondition {
+      lhs,
+      op,
+      rhs,
+      all,
+      any,
+      not,
+    } = RawCondition::deserialize(deserializer)?;
+
+    let keys = [
+      ("lhs", lhs.is_some()),
+      ("op", op.is_some()),
+      ("rhs", rhs.is_some()),
+      ("all", all.is_some()),
+      ("any", any.is_some()),
+      ("not", not.is_some()),
+    ]
+    .into_iter()
+    .filter(|(_, present)| *present)
+    .map(|(key, _)| format!("\"{}\"", key))
+    .collect::<Vec<_>>();
+
+    match (lhs, op, rhs, all, any, not) {
+      (Some(lhs), Some(op), rhs, None, None, None) => Ok(Condition::Compare { lhs, op, rhs }),
+      (None, None, None, Some(all), None, None) => Ok(Condition::All { all }),
+      (None, None, None, None, Some(any), None) => Ok(Condition::Any { any }),
+      (None, None, None, None, None, Some(not)) => Ok(Condition::Not { not }),
+      _ => Err(de::Error::custom(format!(
+        "expected either a comparison (\"lhs\", \"op\" and an optional \"rhs\") or one of \"all\", \"any\" and \"not\", found {}",
+        match keys.is_empty() {
+          true => "no key".into(),
+          false => keys.join(", "),
+        }
+      ))),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub enum Operation {
   // Separate op to avoid ambiguity
-  Copy { file: String, dest: String },
-  CopyDir { path: String, dest: String },
-  Remove { files: Vec<String> },
+  Copy {
+    file: String,
+    dest: String,
+  },
+  CopyDir {
+    path: String,
+    dest: String,
+  },
+  Remove {
+    files: Vec<String>,
+  },
+  /// Renames a file or dir, both paths being interpolated
+  Move {
+    from: String,
+    to: String,
+  },
+  /// Writes the content to dest, which is replaced if it exists. Both are interpolated, the
+  /// written file being left out of the dynamic files
+  Write {
+    dest: String,
+    content: String,
+  },
+  /// Appends the content to dest, which is created if needed. Both are interpolated, the rest of
+  /// dest being only bound if it is a dynamic file
+  Append {
+    dest: String,
+    content: String,
+  },
+  /// Replaces the matches of the regex in the files selected by the globs. The replacement may
+  /// refer to the groups as `$1`, `${1}`, `$name` or `${name}`, to the args the same way as long
+  /// as no group has their name, `$$` being a literal `$`
+  Replace {
+    files: Vec<String>,
+    pattern: String,
+    replacement: String,
+  },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct AdditionalOperation {
   #[serde(default = "Vec::new")]
+  #[schemars(default)]
   pub conditions: Vec<Condition>,
   /// operations to execute if the above conditions are evaluated as true
   pub operations: Vec<Operation>,
+  /// operations to execute otherwise
+  #[serde(default)]
+  pub else_operations: Vec<Operation>,
+}
+
+impl Operation {
+  /// Human readable form of the op, paths are shown relative to `base`
+  pub fn describe(&self, base: &Path) -> String {
+    let relative = |path: &str| {
+      Path::new(path)
+        .strip_prefix(base)
+        .unwrap_or(Path::new(path))
+        .display()
+        .to_string()
+    };
+    match self {
+      Operation::Copy { file, dest } => format!("Copy {} -> {}", relative(file), relative(dest)),
+      Operation::CopyDir { path, dest } => {
+        format!("CopyDir {} -> {}", relative(path), relative(dest))
+      }
+      Operation::Move { from, to } => format!("Move {} -> {}", relative(from), relative(to)),
+      Operation::Write { dest, .. } => format!("Write {}", relative(dest)),
+      Operation::Append { dest, .. } => format!("Append {}", relative(dest)),
+      Operation::Replace { files, pattern, .. } => format!(
+        "Replace /{}/ in {}",
+        pattern,
+        files
+          .iter()
+          .map(|f| relative(f))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+      Operation::Remove { files } => format!(
+        "Remove {}",
+        files
+          .iter()
+          .map(|f| relative(f))
+          .collect::<Vec<_>>()
+          .join(", ")
+      ),
+    }
+  }
+
+  /// File the op fills with bound content, which must not be bound again
+  pub fn bound_file(&self, ctx: &Context) -> Option<PathBuf> {
+    match self {
+      Operation::Write { dest, .. } => Some(PathBuf::from(interpolate(dest, ctx))),
+      _ => None,
+    }
+  }
+}
+
+impl AdditionalOperation {
+  /// Operations to execute given the ctx
+  pub fn selected(&self, ctx: &Context) -> ProplateResult<&[Operation]> {
+    // eval condition or true if it is empty or missing
+    match Condition::all_hold(&self.conditions, ctx)? {
+      true => Ok(&self.operations),
+      false => Ok(&self.else_operations),
+    }
+  }
+
+  /// Operations of both branches
+  pub fn all_operations(&self) -> impl Iterator<Item = &Operation> {
+    self.operations.iter().chain(&self.else_operations)
+  }
+
+  pub fn all_operations_mut(&mut self) -> impl Iterator<Item = &mut Operation> {
+    self
+      .operations
+      .iter_mut()
+      .chain(self.else_operations.iter_mut())
+  }
+}
+
+impl StringCompareOp {
+  /// Compares the operands, failing with the reason they cannot be compared by the op
+  ///
+  /// # Example
+  /// ```
+  /// use proplate_core::{gen::value::Value, template::op::StringCompareOp};
+  ///
+  /// let licenses = Value::from(vec!["MIT".to_string(), "Apache-2.0".to_string()]);
+  /// assert_eq!(StringCompareOp::In.eval(&"MIT".into(), &licenses), Ok(true));
+  /// assert_eq!(StringCompareOp::Gt.eval(&"1.10.0".into(), &"1.9.0".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Gt.eval(&"1.10".into(), &"1.9.0".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Gt.eval(&"0.5".into(), &"0.25".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Lt.eval(&"1.5".into(), &"1.25".into()), Ok(false));
+  /// assert_eq!(StringCompareOp::Eq.eval(&"1.0.0".into(), &"1.0".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Gte.eval(&"1.0.0".into(), &"1.0".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Lte.eval(&"1.0.0".into(), &"1.0".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Lt.eval(&"2.0.0-rc.1".into(), &"2.0.0".into()), Ok(true));
+  /// assert_eq!(StringCompareOp::Gte.eval(&Value::Int(8080), &"1024".into()), Ok(true));
+  /// assert!(StringCompareOp::Gt.eval(&"latest".into(), &"1.0.0".into()).is_err());
+  /// ```
+  pub fn eval(&self, lhs: &Value, rhs: &Value) -> Result<bool, String> {
+    let holds = match self {
+      StringCompareOp::Eq => equals(lhs, rhs),
+      StringCompareOp::NotEqual => !equals(lhs, rhs),
+      StringCompareOp::Contains => lhs.items().iter().any(|item| equals(item, rhs)),
+      StringCompareOp::In => rhs.items().iter().any(|item| equals(lhs, item)),
+      StringCompareOp::NotIn => !rhs.items().iter().any(|item| equals(lhs, item)),
+      StringCompareOp::Matches => Regex::new(&rhs.to_string())
+        .map_err(|e| format!("Invalid pattern '{}': {}", rhs, e))?
+        .is_match(&lhs.to_string()),
+      StringCompareOp::StartsWith => lhs.to_string().starts_with(&rhs.to_string()),
+      StringCompareOp::EndsWith => lhs.to_string().ends_with(&rhs.to_string()),
+      StringCompareOp::IsEmpty => lhs.is_empty(),
+      StringCompareOp::IsNotEmpty => !lhs.is_empty(),
+      StringCompareOp::Gt => compare(lhs, rhs)?.is_gt(),
+      StringCompareOp::Lt => compare(lhs, rhs)?.is_lt(),
+      StringCompareOp::Gte => compare(lhs, rhs)?.is_ge(),
+      StringCompareOp::Lte => compare(lhs, rhs)?.is_le(),
+    };
+    Ok(holds)
+  }
+
+  /// Whether the op compares the lhs to a rhs
+  fn takes_rhs(&self) -> bool {
+    !matches!(self, StringCompareOp::IsEmpty | StringCompareOp::IsNotEmpty)
+  }
+}
+
+/// Loose equality, so that `true` equals "true", `8080` equals "8080.0" and "1.0.0" equals "1.0"
+fn equals(lhs: &Value, rhs: &Value) -> bool {
+  lhs == rhs || lhs.matches(&rhs.to_string()) || compare(lhs, rhs).is_ok_and(Ordering::is_eq)
+}
+
+/// Orders versions such as "1.2.3" and "v2.0", or else numbers. A string is only read as a version
+/// when it looks like one, see [Version::is_explicit], so that "0.5" is greater than "0.25"
+fn compare(lhs: &Value, rhs: &Value) -> Result<Ordering, String> {
+  let as_version = |value: &Value| match value {
+    Value::String(s) => Version::parse(s),
+    Value::Int(i) => Version::parse(&i.to_string()),
+    _ => None,
+  };
+  let is_version = |value: &Value| matches!(value, Value::String(s) if Version::is_explicit(s));
+  if is_version(lhs) || is_version(rhs) {
+    if let (Some(lhs), Some(rhs)) = (as_version(lhs), as_version(rhs)) {
+      return Ok(lhs.cmp(&rhs));
+    }
+  }
+
+  let as_number = |value: &Value| match value {
+    Value::Int(i) => Some(*i as f64),
+    Value::Float(f) => Some(*f),
+    Value::String(s) => s.trim().parse::<f64>().ok(),
+    _ => None,
+  };
+  match (as_number(lhs), as_number(rhs)) {
+    (Some(lhs), Some(rhs)) => lhs
+      .partial_cmp(&rhs)
+      .ok_or_else(|| format!("Cannot compare {} and {}", lhs, rhs)),
+    _ => Err(format!(
+      "Cannot compare '{}' and '{}', expected numbers or versions such as \"1.2.3\"",
+      lhs, rhs
+    )),
+  }
+}
+
+/// Semantic version, a missing minor or patch being 0 and the build metadata being ignored
+#[derive(Debug, PartialEq, Eq)]
+struct Version {
+  core: [u64; 3],
+  pre_release: Vec<String>,
+}
+
+impl Version {
+  /// Whether `source` cannot be a decimal, i.e it has several dots, a "v" prefix or a
+  /// pre-release or build suffix, e.g "1.2.3", "v2" or "2.0-beta"
+  fn is_explicit(source: &str) -> bool {
+    let source = source.trim();
+    source.starts_with('v')
+      || source.matches('.').count() >= 2
+      || source
+        .get(1..)
+        .is_some_and(|rest| rest.contains(['-', '+']))
+  }
+
+  fn parse(source: &str) -> Option<Version> {
+    let source = source.trim();
+    let source = source.strip_prefix('v').unwrap_or(source);
+    let source = source.split('+').next()?;
+    let (core, pre_release) = match source.split_once('-') {
+      Some((core, pre)) => (core, pre.split('.').map(String::from).collect()),
+      None => (source, Vec::new()),
+    };
+
+    let mut numbers = core
+      .split('.')
+      .map(|n| n.parse::<u64>().ok())
+      .collect::<Option<Vec<_>>>()?;
+    if numbers.len() > 3 {
+      return None;
+    }
+    numbers.resize(3, 0);
+    Some(Version {
+      core: numbers.try_into().ok()?,
+      pre_release,
+    })
+  }
+}
+
+impl Ord for Version {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // a pre-release precedes its version
+    let release = |v: &Version| v.pre_release.is_empty();
+    self
+      .core
+      .cmp(&other.core)
+      .then_with(|| match (release(self), release(other)) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => {
+          let identifiers = self.pre_release.iter().zip(&other.pre_release);
+          identifiers
+            .map(|(a, b)| match (a.parse::<u64>(), b.parse::<u64>()) {
+              (Ok(a), Ok(b)) => a.cmp(&b),
+              (Ok(_), Err(_)) => Ordering::Less,
+              (Err(_), Ok(_)) => Ordering::Greater,
+              (Err(_), Err(_)) => a.cmp(b),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| self.pre_release.len().cmp(&other.pre_release.len()))
+        }
+      })
+  }
+}
+
+impl PartialOrd for Version {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
 }
 
 impl Condition {
-  fn eval(&self) -> bool {
-    match self.op {
-      StringCompareOp::Eq => self.lhs == self.rhs,
-      StringCompareOp::NotEqual => self.lhs != self.rhs,
+  /// Whether the condition holds against the ctx, failing when its operands cannot be compared
+  pub fn eval(&self, ctx: &Context) -> ProplateResult<bool> {
+    match self {
+      Condition::Compare { lhs, op, rhs } => {
+        let rhs = match (rhs, op.takes_rhs()) {
+          (Some(rhs), _) => resolve_value(rhs, ctx),
+          (None, false) => Value::String(String::new()),
+          (None, true) => return Err(self.error(&format!("'{:?}' expects a rhs", op))),
+        };
+        op.eval(&resolve(lhs, ctx), &rhs)
+          .map_err(|reason| self.error(&reason))
+      }
+      Condition::All { all } => Condition::all_hold(all, ctx),
+      Condition::Any { any } => {
+        for condition in any {
+          if condition.eval(ctx)? {
+            return Ok(true);
+          }
+        }
+        Ok(false)
+      }
+      Condition::Not { not } => Ok(!not.eval(ctx)?),
+    }
+  }
+
+  /// Whether every condition holds, stopping at the first one which doesn't
+  pub fn all_hold(conditions: &[Condition], ctx: &Context) -> ProplateResult<bool> {
+    for condition in conditions {
+      if !condition.eval(ctx)? {
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+
+  fn error(&self, cause: &str) -> ProplateError {
+    ProplateError::create(ProplateErrorKind::Condition {
+      condition: serde_json::to_string(self).unwrap_or_default(),
+    })
+    .with_ctx("op::Condition::eval")
+    .with_cause(cause)
+  }
+
+  /// lhs and rhs (the strings it holds) of the nested comparisons
+  pub fn operands(&self) -> Vec<&str> {
+    match self {
+      Condition::Compare { lhs, rhs, .. } => {
+        let mut operands = vec![lhs.as_str()];
+        operands.extend(rhs.iter().flat_map(Value::strings));
+        operands
+      }
+      Condition::All { all: conditions } | Condition::Any { any: conditions } => {
+        conditions.iter().flat_map(Condition::operands).collect()
+      }
+      Condition::Not { not } => not.operands(),
+    }
+  }
+}
+
+/// Interpolates the strings the value holds, a lone var keeping the type of its value
+fn resolve_value(value: &Value, ctx: &Context) -> Value {
+  match value {
+    Value::String(s) => resolve(s, ctx),
+    Value::List(items) => Value::List(items.iter().map(|v| resolve_value(v, ctx)).collect()),
+    value => value.clone(),
+  }
+}
+
+/// Files selected by the interpolated globs
+fn glob_files(globs: &[String], ctx: &Context) -> Result<Vec<PathBuf>, String> {
+  let mut selected = Vec::new();
+  for pattern in globs {
+    for path in glob::glob(&interpolate(pattern, ctx)).map_err(|e| e.to_string())? {
+      let path = path.map_err(|e| e.to_string())?;
+      if path.is_file() {
+        selected.push(path);
+      }
     }
   }
+  Ok(selected)
+}
 
-  pub fn eval_in_ctx(&self, ctx: &HashMap<String, String>) -> bool {
-    let mut c = self.clone();
-    c.lhs = c.lhs.interpolate(ctx);
-    c.rhs = c.rhs.interpolate(ctx);
-    c.eval()
+/// Replacement as understood by [Regex::replace_all] whose args are bound. "$1", "${1}", "$$" and
+/// the groups of `re` e.g "$tool" or "${tool}" are kept, any other "$name" or "${...}" refers to
+/// an arg whose value is escaped
+///
+/// With a "tool" group, "${tool}-$version" gives "${tool}-2.0.0"
+fn bind_replacement(replacement: &str, re: &Regex, ctx: &Context) -> String {
+  let is_group = |name: &str| {
+    name.chars().all(|c| c.is_ascii_digit()) || re.capture_names().flatten().any(|g| g == name)
+  };
+
+  let mut result = String::new();
+  let mut rest = replacement;
+  while let Some(at) = rest.find('$') {
+    result.push_str(&rest[..at]);
+    let tail = &rest[at..];
+    let len = match reference(tail) {
+      _ if tail.starts_with("$$") => {
+        result.push_str("$$");
+        2
+      }
+      Some((name, len)) if is_group(name) => {
+        result.push_str(&tail[..len]);
+        len
+      }
+      Some((_, len)) => {
+        result.push_str(&interpolate(&tail[..len], ctx).replace('$', "$$"));
+        len
+      }
+      // a "$" which refers to nothing is literal
+      None => {
+        result.push_str("$$");
+        1
+      }
+    };
+    rest = &tail[len..];
   }
+  result.push_str(rest);
+  result
+}
+
+/// Name `source` starts referring to, as "$name" or "${name}", along with the length of the
+/// reference
+fn reference(source: &str) -> Option<(&str, usize)> {
+  let rest = &source[1..];
+  let (name, len) = match rest.strip_prefix('{') {
+    Some(braced) => {
+      let name = &braced[..braced.find('}')?];
+      (name, name.len() + 3)
+    }
+    None => {
+      let name = rest
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .next()?;
+      (name, name.len() + 1)
+    }
+  };
+  (!name.is_empty()).then_some((name, len))
 }
 
 pub trait Execute {
-  fn execute(&self, ctx: &HashMap<String, String>) -> ProplateResult<()>;
+  fn execute(&self, ctx: &Context) -> ProplateResult<()>;
 }
 
 impl Execute for Operation {
-  fn execute(&self, _ctx: &HashMap<String, String>) -> ProplateResult<()> {
+  fn execute(&self, ctx: &Context) -> ProplateResult<()> {
     match self {
       Operation::Copy { file, dest } => {
         let src = Path::new(&file);
@@ -98,25 +593,75 @@ impl Execute for Operation {
         }
         Ok(())
       }
+      Operation::Move { from, to } => {
+        let from = PathBuf::from(interpolate(from, ctx));
+        let to = PathBuf::from(interpolate(to, ctx));
+        pfs::move_fdir(&from, &to).map_err(|e| {
+          ProplateError::create(ProplateErrorKind::Fs {
+            concerned_paths: vec![from.display().to_string(), to.display().to_string()],
+            operation: "move".into(),
+          })
+          .with_ctx("op::execute::Move")
+          .with_cause(&e.to_string())
+        })?;
+        Ok(())
+      }
+      Operation::Write { dest, content } => {
+        let dest = PathBuf::from(interpolate(dest, ctx));
+        pfs::write_file(&dest, &interpolate(content, ctx), false).map_err(|e| {
+          ProplateError::create(ProplateErrorKind::Fs {
+            concerned_paths: vec![dest.display().to_string()],
+            operation: "write".into(),
+          })
+          .with_ctx("op::execute::Write")
+          .with_cause(&e.to_string())
+        })?;
+        Ok(())
+      }
+      Operation::Append { dest, content } => {
+        let dest = PathBuf::from(interpolate(dest, ctx));
+        pfs::write_file(&dest, &interpolate(content, ctx), true).map_err(|e| {
+          ProplateError::create(ProplateErrorKind::Fs {
+            concerned_paths: vec![dest.display().to_string()],
+            operation: "append".into(),
+          })
+          .with_ctx("op::execute::Append")
+          .with_cause(&e.to_string())
+        })?;
+        Ok(())
+      }
+      Operation::Replace {
+        files,
+        pattern,
+        replacement,
+      } => {
+        let error = |cause: String| {
+          ProplateError::create(ProplateErrorKind::Fs {
+            concerned_paths: files.clone(),
+            operation: "replace".into(),
+          })
+          .with_ctx("op::execute::Replace")
+          .with_cause(&cause)
+        };
+        let re = Regex::new(pattern).map_err(|e| error(e.to_string()))?;
+        let replacement = bind_replacement(replacement, &re, ctx);
+        for path in glob_files(files, ctx).map_err(error)? {
+          pfs::map_file(&path, |content| {
+            re.replace_all(content, replacement.as_str()).into_owned()
+          })
+          .map_err(|e| error(format!("{}: {}", path.display(), e)))?;
+        }
+        Ok(())
+      }
     }
   }
 }
 
 impl Execute for AdditionalOperation {
-  fn execute(&self, ctx: &HashMap<String, String>) -> ProplateResult<()> {
-    // eval condition or true if it is empty or missing
-    let conditions = &self.conditions;
-    let true_ = match conditions.is_empty() {
-      true => true,
-      false => conditions.iter().all(|c| c.eval_in_ctx(ctx)),
-    };
-
-    if true_ {
-      for operation in &self.operations {
-        operation.execute(ctx)?;
-      }
+  fn execute(&self, ctx: &Context) -> ProplateResult<()> {
+    for operation in self.selected(ctx)? {
+      operation.execute(ctx)?;
     }
-
     Ok(())
   }
 }
@@ -15,6 +15,15 @@ use crate::{fs as pfs, template::Template};
 /// Attemps to find a template at the given location
 /// It can be either a local path or a github repo url
 pub fn clone_template(location: &str, dest: &str) -> ProplateResult<Template> {
+  clone_template_at(location, dest, None)
+}
+
+/// Same as `clone_template` but checks out the given commit before reading the template
+pub fn clone_template_at(
+  location: &str,
+  dest: &str,
+  commit: Option<&str>,
+) -> ProplateResult<Template> {
   if !is_valid_location(location) {
     return Err(
       ProplateError::create(ProplateErrorKind::Template {
@@ -25,13 +34,26 @@ pub fn clone_template(location: &str, dest: &str) -> ProplateResult<Template> {
       .with_cause("The location is neither a local nor a git repo"),
     );
   }
-  match is_remote_uri(location) {
-    true => clone_remote_template(location),
-    false => clone_local_template(location, dest),
-  }
+  let (path, id) = match is_remote_uri(location) {
+    true => clone_remote_template(location)?,
+    false => clone_local_template(location, dest)?,
+  };
+
+  (|| {
+    if let Some(commit) = commit {
+      checkout(&path, commit)?;
+    }
+    template_with_filebase(path.clone(), &id, location.into())
+  })()
+  .inspect_err(|_| {
+    // the fork is useless if it can't be read as a template
+    if fs::remove_dir_all(&path).is_err() {
+      println!("{}", logger::warn("Unable to cleanup"));
+    }
+  })
 }
 
-fn clone_local_template(location: &str, dest: &str) -> ProplateResult<Template> {
+fn clone_local_template(location: &str, dest: &str) -> ProplateResult<(PathBuf, String)> {
   // make unique id
   let path = join_path!(".temp", format!("{}-{}", dest, Uuid::new_v4()));
   let from = Path::new(location);
@@ -50,10 +72,10 @@ fn clone_local_template(location: &str, dest: &str) -> ProplateResult<Template>
     .with_cause(&e.to_string())
   })?;
 
-  template_with_filebase(path.into(), location, location.into())
+  Ok((path, location.into()))
 }
 
-fn clone_remote_template(uri: &str) -> ProplateResult<Template> {
+fn clone_remote_template(uri: &str) -> ProplateResult<(PathBuf, String)> {
   let tail = uri.strip_prefix("https://github.com/").unwrap();
 
   // make unique id
@@ -79,7 +101,30 @@ fn clone_remote_template(uri: &str) -> ProplateResult<Template> {
     .with_cause("git clone failed")
   })?;
 
-  template_with_filebase(dest, &id, uri.to_string())
+  Ok((dest, id))
+}
+
+fn checkout(path: &Path, commit: &str) -> ProplateResult<()> {
+  println!("{}", logger::step(&format!("Checking out {}...", commit)));
+  if !path.join(".git").exists() {
+    return Err(
+      ProplateError::create(ProplateErrorKind::Fs {
+        concerned_paths: vec![path.display().to_string()],
+        operation: "checkout".into(),
+      })
+      .with_ctx("template:checkout")
+      .with_cause("The template is not a git repo"),
+    );
+  }
+  git::exec_cmd(["checkout", "--quiet", commit], path)
+}
+
+/// HEAD of the template repo, `None` if it isn't one
+fn resolve_commit(path: &Path) -> Option<String> {
+  match path.join(".git").exists() {
+    true => git::exec_cmd_output(["rev-parse", "HEAD"], path).ok(),
+    false => None,
+  }
 }
 
 // TODO: move to Template struct
@@ -94,13 +139,15 @@ fn template_with_filebase(path: PathBuf, id: &str, source: String) -> ProplateRe
       .with_ctx("template:create")
       .with_cause(&e.to_string())
     })?
-    .into_iter()
     .filter_map(|e| match e {
       Ok(entry) => entry.file_name().to_str().map(|s| s.to_string()).or(None),
       _ => None,
     })
     .collect::<Vec<_>>();
-  Ok(Template::build(id.to_string(), path, file_list, source))
+  let commit = resolve_commit(&path);
+  let mut template = Template::build(id.to_string(), path, file_list, source)?;
+  template.commit = commit;
+  Ok(template)
 }
 
 fn is_remote_uri(uri: &str) -> bool {
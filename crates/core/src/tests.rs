@@ -1,5 +1,9 @@
 #[cfg(test)]
 mod gen_test;
+#[cfg(test)]
+mod lint_test;
+#[cfg(test)]
+mod schema_test;
 
 use std::{
   fs,
@@ -58,11 +62,8 @@ fn cleanup_test_trash() {
 }
 
 fn run_isolated_test(f: impl Fn() -> ProplateResult<()>, _clean: bool) {
-  match f() {
-    Err(e) => {
-      panic!("{}: {}:{}", e.print_err(), line!(), column!())
-    }
-    _ => (),
+  if let Err(e) = f() {
+    panic!("{}: {}:{}", e.print_err(), line!(), column!())
   }
   // rust tests run in parallel
   // fixme: sadly.... WE CANNOT CLEANUP THE TRASH... as some tests may still use it
@@ -72,9 +73,9 @@ fn run_isolated_test(f: impl Fn() -> ProplateResult<()>, _clean: bool) {
 // TODO: some op are weird
 fn assert_dir_superset(dir1: &Path, dir2: &Path) -> std::io::Result<()> {
   for (file, relative) in walk_dir(dir1)? {
-    let a = fs::read_to_string(&file).expect(format!("Fail {}", file.display()).as_str());
+    let a = fs::read_to_string(&file).unwrap_or_else(|_| panic!("Fail {}", file.display()));
     let b = fs::read_to_string(dir2.join(&relative))
-      .expect(format!("Fail {}", dir2.join(&relative).display()).as_str());
+      .unwrap_or_else(|_| panic!("Fail {}", dir2.join(&relative).display()));
     assert_eq!(a, b);
   }
   Ok(())
@@ -111,7 +112,7 @@ macro_rules! test_create {
     let (t, snap) = get_fixture($pkg, $name);
 
     let mut fork = clone_template(t.display().to_string().as_str(), &dest)?;
-    bootstrap(&mut fork, &dest, &$ctx)?;
+    bootstrap(&mut fork, &dest, &$ctx, &Default::default())?;
 
     (path, snap)
   }};
@@ -31,24 +31,29 @@ mod exclude_files {
 
 #[cfg(test)]
 mod dynamic_files {
-  use std::collections::HashMap;
+  use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+  };
 
   use crate::{
     assert_gen_ok, assert_gen_snapshot,
-    gen::bootstrap::bootstrap,
+    gen::{bootstrap::bootstrap, value::Value},
     template::{resolver::clone_template, META_CONF},
     test_create,
     tests::{assert_dir_superset, get_fixture, new_trash, run_isolated_test},
   };
+  use proplate_errors::ProplateResult;
+  use proplate_tui::logger::AsError;
 
   #[test]
   fn empty_dyn_file() {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("name".to_string(), "empty-dyn-file".to_string()),
-          ("ver".to_string(), "1.0.0".to_string()),
-          ("file_structure".to_string(), "module".to_string()),
+          ("name".to_string(), "empty-dyn-file".into()),
+          ("ver".to_string(), "1.0.0".into()),
+          ("file_structure".to_string(), "module".into()),
         ]);
 
         let (path, snap) = test_create!("dynamic_files", "empty-dyn-file", ctx);
@@ -67,9 +72,9 @@ mod dynamic_files {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("name".to_string(), "only-pkg".to_string()),
-          ("ver".to_string(), "1.0.0".to_string()),
-          ("file_structure".to_string(), "module".to_string()),
+          ("name".to_string(), "only-pkg".into()),
+          ("ver".to_string(), "1.0.0".into()),
+          ("file_structure".to_string(), "module".into()),
         ]);
 
         let (path, snap) = test_create!("dynamic_files", "only-pkg", ctx);
@@ -83,14 +88,36 @@ mod dynamic_files {
     );
   }
 
+  #[test]
+  fn listed_files_are_bound_once() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "a$name".into()),
+          ("ver".to_string(), "1.0.0".into()),
+          ("file_structure".to_string(), "module".into()),
+        ]);
+
+        let (path, _) = test_create!("dynamic_files", "only-pkg", ctx);
+
+        assert_gen_ok!(&path);
+        let package = fs::read_to_string(path.join("package.json")).unwrap();
+        assert!(package.contains(r#""name": "a$name","#));
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
   #[test]
   fn select_both() {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("name".to_string(), "select-both".to_string()),
-          ("ver".to_string(), "1.0.0".to_string()),
-          ("file_structure".to_string(), "commonjs".to_string()),
+          ("name".to_string(), "select-both".into()),
+          ("ver".to_string(), "1.0.0".into()),
+          ("file_structure".to_string(), "commonjs".into()),
         ]);
 
         let (path, snap) = test_create!("dynamic_files", "select-both", ctx);
@@ -109,10 +136,10 @@ mod dynamic_files {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("dyn0".to_string(), "val0".to_string()),
-          ("d0".to_string(), "v0".to_string()),
-          ("d1".to_string(), "v1".to_string()),
-          ("d2".to_string(), "v2".to_string()),
+          ("dyn0".to_string(), "val0".into()),
+          ("d0".to_string(), "v0".into()),
+          ("d1".to_string(), "v1".into()),
+          ("d2".to_string(), "v2".into()),
         ]);
 
         let (path, snap) = test_create!("dynamic_files", "recursive-dyn-file", ctx);
@@ -125,11 +152,202 @@ mod dynamic_files {
       /*clean*/ false,
     );
   }
+
+  #[test]
+  fn braced() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "proplate-core".into()),
+          ("padded".to_string(), "  Trimmed  ".into()),
+        ]);
+
+        let (path, snap) = test_create!("dynamic_files", "braced", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn typed() {
+    run_isolated_test(
+      || {
+        let service = |name: &str, replicas: i64| {
+          Value::Map(BTreeMap::from([
+            ("name".to_string(), name.into()),
+            ("replicas".to_string(), replicas.into()),
+          ]))
+        };
+        let ctx = HashMap::from([
+          ("name".to_string(), "typed".into()),
+          ("docker".to_string(), true.into()),
+          (
+            "db".to_string(),
+            Value::Map(BTreeMap::from([("port".to_string(), 5432.into())])),
+          ),
+          (
+            "services".to_string(),
+            Value::List(vec![service("api", 2), service("worker", 1)]),
+          ),
+        ]);
+
+        let (path, snap) = test_create!("dynamic_files", "typed", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn computed() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("project_name".to_string(), "my-app".into())]);
+
+        let (path, snap) = test_create!("dynamic_files", "computed", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn computed_cycle() {
+    let err = (|| -> ProplateResult<_> {
+      Ok(test_create!(
+        "dynamic_files",
+        "computed-cycle",
+        HashMap::new()
+      ))
+    })()
+    .expect_err("cyclic computed vars");
+    let msg = err.print_err();
+
+    assert!(msg.contains("cannot compute 'a'"));
+    assert!(msg.contains("Cycle: a -> b -> c -> a"));
+  }
+
+  #[test]
+  fn strict() {
+    let ctx = HashMap::from([("name".to_string(), "proplate".into())]);
+
+    let err = (|| -> ProplateResult<_> { Ok(test_create!("dynamic_files", "strict", ctx)) })()
+      .expect_err("strict template with unresolved vars");
+    let msg = err.print_err();
+
+    assert!(msg.contains("README.md:3:4: $autor ('autor' is undefined)"));
+    assert!(
+      msg.contains("README.md:4:1: ${name | unknown_filter} (Unknown filter 'unknown_filter')")
+    );
+    assert!(!msg.contains("escaped"));
+    assert!(!msg.contains("license"));
+    // paths are checked as well, once per dir
+    assert!(msg.contains("docs/$topic: $topic ('topic' is undefined)"));
+    assert_eq!(msg.matches("$topic (").count(), 1);
+    assert!(!msg.contains("src/$name.rs"));
+  }
+
+  #[test]
+  fn blocks() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "proplate".into()),
+          ("license".to_string(), "BSD".into()),
+          ("features".to_string(), "cli, tui".into()),
+        ]);
+
+        let (path, snap) = test_create!("dynamic_files", "blocks", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn templated_paths() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "my-app".into()),
+          ("module_name".to_string(), "auth".into()),
+        ]);
+
+        let (path, snap) = test_create!("dynamic_files", "templated-paths", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn path_collision() {
+    let ctx = HashMap::from([
+      ("first".to_string(), "same".into()),
+      ("second".to_string(), "same".into()),
+    ]);
+
+    let err =
+      (|| -> ProplateResult<_> { Ok(test_create!("dynamic_files", "path-collision", ctx)) })()
+        .expect_err("paths resolving to the same file");
+
+    assert!(err.print_err().contains("Both paths resolve to 'same.txt'"));
+  }
+
+  #[test]
+  fn empty_path_segment() {
+    let ctx = HashMap::new();
+
+    let err =
+      (|| -> ProplateResult<_> { Ok(test_create!("dynamic_files", "empty-segment", ctx)) })()
+        .expect_err("dir name bound to an empty string");
+
+    assert!(err
+      .print_err()
+      .contains("'$dir' resolves to '' which is not a valid file name"));
+  }
+
+  #[test]
+  fn unclosed_block() {
+    let err = (|| -> ProplateResult<_> {
+      Ok(test_create!(
+        "dynamic_files",
+        "unclosed-block",
+        HashMap::new()
+      ))
+    })()
+    .expect_err("unclosed block");
+
+    assert!(err
+      .print_err()
+      .contains("README.md:3:1: Unclosed if block, expected '{% endif %}'"));
+  }
 }
 
 #[cfg(test)]
 mod additional_ops {
-  use std::collections::HashMap;
+  use std::{collections::HashMap, fs};
 
   use crate::{
     assert_gen_ok, assert_gen_snapshot,
@@ -138,15 +356,17 @@ mod additional_ops {
     test_create,
     tests::{assert_dir_superset, get_fixture, new_trash, run_isolated_test},
   };
+  use proplate_errors::ProplateResult;
+  use proplate_tui::logger::AsError;
 
   #[test]
   fn unlicensed() {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("project_name".to_string(), "unlicensed".to_string()),
-          ("author_name".to_string(), "Proplate".to_string()),
-          ("license".to_string(), "UNLICENSED".to_string()),
+          ("project_name".to_string(), "unlicensed".into()),
+          ("author_name".to_string(), "Proplate".into()),
+          ("license".to_string(), "UNLICENSED".into()),
         ]);
 
         let (path, _) = test_create!("additional_ops", "conditional-license", ctx);
@@ -162,14 +382,133 @@ mod additional_ops {
     );
   }
 
+  #[test]
+  fn else_operations() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("license".to_string(), "Apache-2.0".into())]);
+
+        let (path, _) = test_create!("additional_ops", "license-choice", ctx);
+        let (snap, _) = get_fixture("additional_ops", "apache-license-snapshot");
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn nested_conditions() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("license".to_string(), "None".into())]);
+
+        let (path, _) = test_create!("additional_ops", "license-choice", ctx);
+        let (snap, _) = get_fixture("additional_ops", "no-license-snapshot");
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn rich_conditions() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "@proplate/cli".into()),
+          ("node_version".to_string(), "v20.1.0".into()),
+          ("license".to_string(), "MIT".into()),
+        ]);
+
+        let (path, snap) = test_create!("additional_ops", "rich-conditions", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn incomparable_operands() {
+    let ctx = HashMap::from([
+      ("name".to_string(), "proplate".into()),
+      ("node_version".to_string(), "latest".into()),
+      ("license".to_string(), "MIT".into()),
+    ]);
+
+    let err =
+      (|| -> ProplateResult<_> { Ok(test_create!("additional_ops", "rich-conditions", ctx)) })()
+        .expect_err("'latest' is not a version");
+    let msg = err.print_err();
+
+    assert!(msg.contains("cannot be evaluated"));
+    assert!(msg.contains("Cannot compare 'latest' and '18.0.0'"));
+  }
+
+  #[test]
+  fn confirm() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("project_name".to_string(), "docker".into()),
+          ("docker".to_string(), "true".into()),
+        ]);
+
+        let (path, _) = test_create!("additional_ops", "confirm-docker", ctx);
+        let (snap, _) = get_fixture("additional_ops", "docker-snapshot");
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn multi_select() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("project_name".to_string(), "tools".into()),
+          (
+            "tools".to_string(),
+            vec!["eslint".to_string(), "prettier".to_string()].into(),
+          ),
+        ]);
+
+        let (path, _) = test_create!("additional_ops", "multi-select-tools", ctx);
+        let (snap, _) = get_fixture("additional_ops", "tools-snapshot");
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
   #[test]
   fn bsd_2_clause() {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("project_name".to_string(), "bsd-2-clause".to_string()),
-          ("author_name".to_string(), "Proplate".to_string()),
-          ("license".to_string(), "BSD-2-Clause".to_string()),
+          ("project_name".to_string(), "bsd-2-clause".into()),
+          ("author_name".to_string(), "Proplate".into()),
+          ("license".to_string(), "BSD-2-Clause".into()),
         ]);
 
         let (path, _) = test_create!("additional_ops", "conditional-license", ctx);
@@ -190,9 +529,9 @@ mod additional_ops {
     run_isolated_test(
       || {
         let ctx = HashMap::from([
-          ("project_name".to_string(), "mit".to_string()),
-          ("author_name".to_string(), "Proplate".to_string()),
-          ("license".to_string(), "MIT".to_string()),
+          ("project_name".to_string(), "mit".into()),
+          ("author_name".to_string(), "Proplate".into()),
+          ("license".to_string(), "MIT".into()),
         ]);
 
         let (path, _) = test_create!("additional_ops", "conditional-license", ctx);
@@ -224,4 +563,236 @@ mod additional_ops {
       /*clean*/ true,
     );
   }
+
+  #[test]
+  fn move_op() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("name".to_string(), "proplate".into())]);
+
+        let (path, snap) = test_create!("additional_ops", "move", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn write_and_append() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("name".to_string(), "proplate".into())]);
+
+        let (path, snap) = test_create!("additional_ops", "write", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn written_content_is_bound_once() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("name".to_string(), "a$name".into())]);
+
+        let (path, _) = test_create!("additional_ops", "write", ctx);
+
+        assert_gen_ok!(&path);
+        assert_eq!(
+          fs::read_to_string(path.join("app.env")).unwrap(),
+          "APP_NAME=a$name\n"
+        );
+        assert_eq!(
+          fs::read_to_string(path.join("README.md")).unwrap(),
+          "# a$name\n"
+        );
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn append_keeps_existing_content() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("name".to_string(), "proplate".into())]);
+
+        // ".gitignore" isn't a dynamic file, its "$RECYCLE.BIN/" is no var
+        let (path, snap) = test_create!("additional_ops", "append", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn replace() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("version".to_string(), "2.0.0".into())]);
+
+        let (path, snap) = test_create!("additional_ops", "replace", ctx);
+
+        assert_gen_ok!(&path);
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn replacement_is_bound_once() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([("version".to_string(), "v$version".into())]);
+
+        let (path, _) = test_create!("additional_ops", "replace", ctx);
+
+        assert_gen_ok!(&path);
+        assert_eq!(
+          fs::read_to_string(path.join("vendor/tool.toml")).unwrap(),
+          "tool = \"formatter-v$version\"\ntool_version = \"v$version\"\nhome = \"$HOME\"\n"
+        );
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+}
+
+#[cfg(test)]
+mod update {
+  use std::{fs, path::PathBuf};
+
+  use crate::{
+    gen::update::merge_update,
+    tests::{new_trash, run_isolated_test},
+  };
+
+  #[test]
+  fn three_way_merge() {
+    run_isolated_test(
+      || {
+        let (root, _) = new_trash();
+        let (old, new, project) = (root.join("old"), root.join("new"), root.join("project"));
+        for dir in [&old, &new, &project] {
+          fs::create_dir_all(dir).unwrap();
+        }
+
+        let files = [
+          // (name, old, new, project)
+          ("untouched", Some("a\n"), Some("b\n"), Some("a\n")),
+          (
+            "merged",
+            Some("1\n2\n3\n"),
+            Some("1\n2\n3\n4\n"),
+            Some("0\n1\n2\n3\n"),
+          ),
+          ("conflict", Some("a\n"), Some("b\n"), Some("c\n")),
+          ("added", None, Some("new\n"), None),
+          ("removed", Some("a\n"), None, Some("a\n")),
+          ("kept", Some("a\n"), None, Some("b\n")),
+          ("deleted", Some("a\n"), Some("b\n"), None),
+        ];
+        for (name, o, n, p) in files {
+          for (dir, content) in [(&old, o), (&new, n), (&project, p)] {
+            if let Some(content) = content {
+              fs::write(dir.join(name), content).unwrap();
+            }
+          }
+        }
+
+        let report = merge_update(&project, &old, &new)?;
+        let read = |name: &str| fs::read_to_string(project.join(name)).unwrap();
+
+        assert_eq!(read("untouched"), "b\n");
+        assert_eq!(read("merged"), "0\n1\n2\n3\n4\n");
+        assert!(read("conflict").contains("<<<<<<<"));
+        assert_eq!(read("added"), "new\n");
+        assert!(!project.join("removed").exists());
+        assert_eq!(read("kept"), "b\n");
+        assert_eq!(read("deleted.rej"), "b\n");
+
+        assert_eq!(report.conflicts, vec![PathBuf::from("conflict")]);
+        assert_eq!(report.rejected, vec![PathBuf::from("deleted")]);
+        assert_eq!(report.kept, vec![PathBuf::from("kept")]);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+}
+
+#[cfg(test)]
+mod manifests {
+  use std::collections::HashMap;
+
+  use crate::{
+    assert_gen_ok, assert_gen_snapshot,
+    gen::bootstrap::bootstrap,
+    template::{resolver::clone_template, META_CONF},
+    test_create,
+    tests::{assert_dir_superset, get_fixture, new_trash, run_isolated_test},
+  };
+
+  #[test]
+  fn yaml() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "yaml-pkg".into()),
+          ("file_structure".to_string(), "module".into()),
+        ]);
+
+        let (path, snap) = test_create!("manifests", "yaml", ctx);
+
+        assert_gen_ok!(&path);
+        assert!(!path.join("meta.yaml").exists());
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
+
+  #[test]
+  fn toml() {
+    run_isolated_test(
+      || {
+        let ctx = HashMap::from([
+          ("name".to_string(), "toml-pkg".into()),
+          ("file_structure".to_string(), "module".into()),
+        ]);
+
+        let (path, snap) = test_create!("manifests", "toml", ctx);
+
+        assert_gen_ok!(&path);
+        assert!(!path.join("meta.toml").exists());
+        assert_gen_snapshot!(&snap, &path);
+
+        Ok(())
+      },
+      /*clean*/ false,
+    );
+  }
 }
@@ -0,0 +1,85 @@
+use crate::{
+  template::lint::{lint, Severity},
+  tests::get_fixture,
+};
+
+#[test]
+fn valid() {
+  let (path, _) = get_fixture("lint", "valid");
+  assert_eq!(lint(&path), vec![]);
+}
+
+#[test]
+fn broken() {
+  let (path, _) = get_fixture("lint", "broken");
+  let diagnostics = lint(&path)
+    .into_iter()
+    .map(|d| (d.severity, d.message))
+    .collect::<Vec<_>>();
+
+  let expected = [
+    (Severity::Error, "Arg 'license' is a Select without options"),
+    (Severity::Error, "Remove target 'ghost' does not exist"),
+    (Severity::Error, "CopyDir source 'nowhere' does not exist"),
+    (Severity::Error, "Dynamic file 'missing.md' does not exist"),
+    (
+      Severity::Error,
+      "Arg 'unused' refers to undeclared 'licence'",
+    ),
+    (
+      Severity::Error,
+      "Computed 'slug' refers to undeclared 'nam'",
+    ),
+    (
+      Severity::Error,
+      "Condition of additional_operations[0] refers to undeclared 'kind'",
+    ),
+    (
+      Severity::Warning,
+      ".proplate_aux_utils/MIT:1:22: 'holder' refers to no declared arg",
+    ),
+    (
+      Severity::Warning,
+      "README.md:3:10: 'author' refers to no declared arg",
+    ),
+    (Severity::Warning, "'unused' is never used"),
+  ]
+  .map(|(severity, message)| (severity, message.to_string()));
+  assert_eq!(diagnostics, expected);
+}
+
+#[test]
+fn several_manifests() {
+  let (path, _) = get_fixture("manifests", "several");
+  let diagnostics = lint(&path);
+
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(
+    diagnostics[0].to_string(),
+    "error: Only one manifest is allowed, found meta.json, meta.yml"
+  );
+}
+
+#[test]
+fn invalid_replace_pattern() {
+  let (path, _) = get_fixture("lint", "bad-pattern");
+  let diagnostics = lint(&path);
+
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(
+    diagnostics[0].to_string(),
+    "error: meta.json: Replace pattern '(v\\d+' is not a valid regex (unclosed group)"
+  );
+}
+
+#[test]
+fn invalid_arg_pattern() {
+  let (path, _) = get_fixture("lint", "bad-arg-pattern");
+  let diagnostics = lint(&path);
+
+  assert_eq!(diagnostics.len(), 1);
+  assert_eq!(
+    diagnostics[0].to_string(),
+    "error: meta.json: Arg 'name' pattern '[a-z' is not a valid regex (unclosed character class)"
+  );
+}
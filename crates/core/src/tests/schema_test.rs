@@ -0,0 +1,114 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+  template::{
+    config::{json_schema, link_schema, read_config},
+    SCHEMA_URL,
+  },
+  tests::{get_path, new_trash},
+};
+
+/// "meta.schema.json" is what editors are pointed to, it has to be regenerated with
+/// `proplate schema > meta.schema.json` whenever the config changes
+#[test]
+fn published_schema_is_up_to_date() {
+  let published = fs::read_to_string(get_path("meta.schema.json")).unwrap();
+  assert_eq!(published.trim_end(), json_schema());
+}
+
+fn trash_with(manifest: &str, content: &str) -> PathBuf {
+  let (path, _) = new_trash();
+  fs::create_dir_all(&path).unwrap();
+  fs::write(path.join(manifest), content).unwrap();
+  path
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+  let path = trash_with(
+    "meta.json",
+    r#"{ "id": "typo", "args": [], "dynamic_file": ["README.md"] }"#,
+  );
+  let reason = read_config(&path, "meta.json").unwrap_err();
+  assert!(reason.starts_with("meta.json:1:42: unknown field `dynamic_file`"));
+
+  let path = trash_with(
+    "meta.json",
+    r#"{ "id": "typo", "args": [], "additional_operations": [{ "operations": [{ "Remove": { "file": ["a"] } }] }] }"#,
+  );
+  let reason = read_config(&path, "meta.json").unwrap_err();
+  assert!(reason.contains("unknown field `file`, expected `files`"));
+
+  let path = trash_with(
+    "meta.json",
+    r#"{ "id": "typo", "args": [{ "key": "a", "q_type": "Text", "label": "A", "patern": "\d+" }] }"#,
+  );
+  let reason = read_config(&path, "meta.json").unwrap_err();
+  assert!(reason.starts_with("meta.json:1:79: unknown field `patern`"));
+}
+
+#[test]
+fn malformed_conditions_are_reported() {
+  let parse = |condition: &str| {
+    let path = trash_with(
+      "meta.json",
+      &format!(
+        r#"{{ "id": "cond", "args": [], "additional_operations": [{{ "conditions": [{}], "operations": [] }}] }}"#,
+        condition
+      ),
+    );
+    read_config(&path, "meta.json")
+  };
+  let reason = |condition: &str| parse(condition).unwrap_err();
+
+  assert!(parse(r#"{ "not": { "any": [{ "lhs": "$a", "op": "IsEmpty" }] } }"#).is_ok());
+  assert!(
+    reason(r#"{ "lhs": "$license", "op": "Eqq", "rhs": "MIT" }"#)
+      .contains("unknown variant `Eqq`, expected one of `Eq`, `NotEqual`")
+  );
+  assert!(reason(r#"{ "lhs": "$license", "op": "Eq", "rsh": "MIT" }"#)
+    .contains("unknown field `rsh`, expected one of `lhs`, `op`, `rhs`, `all`, `any`, `not`"));
+  assert!(reason(r#"{ "all": [], "any": [] }"#).contains(
+    r#"expected either a comparison ("lhs", "op" and an optional "rhs") or one of "all", "any" and "not", found "all", "any""#
+  ));
+  assert!(reason(r#"{ "lhs": "$license", "rhs": "MIT" }"#).ends_with(r#"found "lhs", "rhs""#));
+  assert!(reason("{}").ends_with("found no key"));
+}
+
+#[test]
+fn link_json_schema() {
+  let path = trash_with(
+    "meta.json",
+    "{\n  \"id\": \"linked\",\n  \"args\": [],\n  \"dynamic_files\": [\"README.md\"]\n}\n",
+  );
+  link_schema(&path).unwrap();
+  link_schema(&path).unwrap();
+
+  let linked = fs::read_to_string(path.join("meta.json")).unwrap();
+  let expected = format!(
+    "{{\n  \"$schema\": \"{}\",\n  \"id\": \"linked\",\n  \"args\": [],\n  \"dynamic_files\": [\n    \"README.md\"\n  ]\n}}\n",
+    SCHEMA_URL
+  );
+  assert_eq!(linked, expected);
+  assert!(read_config(&path, "meta.json").is_ok());
+}
+
+#[test]
+fn link_yaml_and_toml_schema() {
+  for (manifest, content, comment) in [
+    (
+      "meta.yaml",
+      "id: linked\nargs: []\n",
+      "# yaml-language-server: $schema=",
+    ),
+    ("meta.toml", "id = \"linked\"\nargs = []\n", "#:schema "),
+  ] {
+    let path = trash_with(manifest, content);
+    link_schema(&path).unwrap();
+    link_schema(&path).unwrap();
+
+    let linked = fs::read_to_string(path.join(manifest)).unwrap();
+    assert_eq!(linked, format!("{}{}\n{}", comment, SCHEMA_URL, content));
+    assert!(read_config(&path, manifest).is_ok());
+  }
+}
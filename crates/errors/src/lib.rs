@@ -1,11 +1,31 @@
+use std::fmt::Display;
+
 use owo_colors::OwoColorize;
 use proplate_tui::logger::{self, AsError};
 
 #[derive(Debug, Clone)]
 pub enum TemplateErrorKind {
-  NotFound { is_remote: bool },
+  NotFound {
+    is_remote: bool,
+  },
   Invalid,
   NoConfig,
+  /// Vars that cannot be bound in strict mode, as "file:line:column: var (reason)"
+  UnresolvedVars {
+    occurrences: Vec<String>,
+  },
+  /// "computed" var that cannot be evaluated
+  Computed {
+    key: String,
+  },
+  /// Block tag that cannot be parsed, as "file:line:column: reason"
+  InvalidBlock {
+    occurrence: String,
+  },
+  /// Errors reported by `proplate validate`
+  Diagnostics {
+    errors: usize,
+  },
 }
 
 #[derive(Debug, Clone)]
@@ -13,9 +33,32 @@ pub enum CliErrorKind {
   Prompt,
 }
 
+#[derive(Debug, Clone)]
+pub enum ArgErrorKind {
+  /// No value was supplied and the arg cannot be prompted
+  Missing,
+  InvalidValue {
+    value: String,
+  },
+  /// Not of the form "key=value"
+  Malformed,
+}
+
 #[derive(Debug, Clone)]
 pub enum ProplateErrorKind {
   Cli(CliErrorKind),
+  Arg {
+    key: String,
+    kind: ArgErrorKind,
+  },
+  /// Condition whose operands cannot be compared, as json
+  Condition {
+    condition: String,
+  },
+  /// Answers file which couldn't be read
+  Answers {
+    location: String,
+  },
   Template {
     kind: TemplateErrorKind,
     location: String,
@@ -30,15 +73,18 @@ pub enum ProplateErrorKind {
   },
 }
 
-impl ToString for ProplateErrorKind {
-  fn to_string(&self) -> String {
+impl Display for ProplateErrorKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     let str = match self {
       ProplateErrorKind::Cli(_) => "Cli",
+      ProplateErrorKind::Arg { .. } => "Arg",
+      ProplateErrorKind::Condition { .. } => "Condition",
+      ProplateErrorKind::Answers { .. } => "Answers",
       ProplateErrorKind::Template { .. } => "Template",
       ProplateErrorKind::Fs { .. } => "Fs",
       ProplateErrorKind::Git { .. } => "Git",
     };
-    str.into()
+    f.write_str(str)
   }
 }
 
@@ -93,18 +139,57 @@ impl AsError for ProplateError {
             .into()
         }
         TemplateErrorKind::Invalid => {
-          format!("template at '{}' config (meta.json) is not valid", location)
+          format!("template at '{}' config (manifest) is not valid", location)
         }
 
         TemplateErrorKind::NoConfig => {
           format!("template at '{}' has no config file", location)
         }
+
+        TemplateErrorKind::Computed { key } => {
+          format!("template at '{}' cannot compute '{}'", location, key)
+        }
+
+        TemplateErrorKind::InvalidBlock { occurrence } => format!(
+          "template at '{}' has an invalid block\n\n- {}",
+          location, occurrence
+        ),
+
+        TemplateErrorKind::Diagnostics { errors } => {
+          format!("template at '{}' has {} error(s)", location, errors)
+        }
+
+        TemplateErrorKind::UnresolvedVars { occurrences } => format!(
+          "template at '{}' has vars that cannot be bound\n\n{}",
+          location,
+          occurrences
+            .iter()
+            .map(|o| format!("- {}", o))
+            .collect::<Vec<_>>()
+            .join("\n")
+        ),
       },
 
       ProplateErrorKind::Cli(kind) => match kind {
-        CliErrorKind::Prompt => format!("a problem occured when prompting the user"),
+        CliErrorKind::Prompt => "a problem occured when prompting the user".to_string(),
       },
 
+      ProplateErrorKind::Arg { key, kind } => match kind {
+        ArgErrorKind::Missing => format!("no value was provided for arg '{}'", key),
+        ArgErrorKind::InvalidValue { value } => {
+          format!("value '{}' is not valid for arg '{}'", value, key)
+        }
+        ArgErrorKind::Malformed => format!("arg '{}' is not of the form 'key=value'", key),
+      },
+
+      ProplateErrorKind::Condition { condition } => {
+        format!("condition '{}' cannot be evaluated", condition)
+      }
+
+      ProplateErrorKind::Answers { location } => {
+        format!("answers file at '{}' cannot be read", location)
+      }
+
       ProplateErrorKind::Fs {
         concerned_paths,
         operation,
@@ -123,7 +208,7 @@ impl AsError for ProplateError {
       }
     };
 
-    let kind = format!("Error: `{}`", self.kind.to_string());
+    let kind = format!("Error: `{}`", self.kind);
     let ctx = match self.ctx.clone() {
       Some(_ctx) => format!("\n\nCtx: {}", &_ctx.bold()),
       _ => "".into(),
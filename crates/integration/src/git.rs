@@ -1,6 +1,6 @@
 use std::{
   path::Path,
-  process::{Command, Stdio},
+  process::{Command, Output, Stdio},
 };
 
 use proplate_errors::{ProplateError, ProplateErrorKind, ProplateResult};
@@ -10,6 +10,26 @@ pub fn exec_cmd<'a, I: IntoIterator<Item = &'a str> + Copy>(
   cmd: I,
   path: &Path,
 ) -> ProplateResult<()> {
+  let output = run(cmd, path)?;
+  if !output.stdout.is_empty() {
+    println!(
+      "{}",
+      logger::success(&String::from_utf8_lossy(&output.stdout))
+    );
+  }
+  Ok(())
+}
+
+/// Same as `exec_cmd` but returns the trimmed stdout instead of printing it
+pub fn exec_cmd_output<'a, I: IntoIterator<Item = &'a str> + Copy>(
+  cmd: I,
+  path: &Path,
+) -> ProplateResult<String> {
+  let output = run(cmd, path)?;
+  Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run<'a, I: IntoIterator<Item = &'a str> + Copy>(cmd: I, path: &Path) -> ProplateResult<Output> {
   let subcmd = cmd.into_iter().next().unwrap();
 
   let child = Command::new("git")
@@ -33,15 +53,7 @@ pub fn exec_cmd<'a, I: IntoIterator<Item = &'a str> + Copy>(
   })?;
 
   match output.status.success() {
-    true => {
-      if !output.stdout.is_empty() {
-        println!(
-          "{}",
-          logger::success(&String::from_utf8_lossy(&output.stdout))
-        );
-      }
-      Ok(())
-    }
+    true => Ok(output),
     _ => Err(ProplateError::create(ProplateErrorKind::Git {
       cmd: subcmd.into(),
       raw_stderr: String::from_utf8_lossy(&output.stderr).into(),
@@ -0,0 +1 @@
+//! proplate
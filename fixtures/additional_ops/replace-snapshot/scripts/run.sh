@@ -0,0 +1,4 @@
+echo $HOME v1
+{% if ci %}
+export CI=1
+{% endif %}
@@ -0,0 +1 @@
+pub fn $module_name() {}
@@ -0,0 +1 @@
+console.log('Generated by $name');